@@ -17,60 +17,66 @@ async fn main() -> Result<()> {
             title: "Audio Test 10s".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 10,
-            background_color: "#1a1a1a".to_string(),
-            text_color: "#e74c3c".to_string(),
+            background_color: "#1a1a1a".parse()?,
+            text_color: "#e74c3c".parse()?,
             font_size: 48,
             audio_track: Some("test_tone_10s.wav".to_string()),
+            ..Default::default()
         },
         // Test 2: 15-second video with 15-second audio
         VideoSpec {
             title: "Audio Test 15s".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 15,
-            background_color: "#3498db".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#3498db".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 56,
             audio_track: Some("test_tone_15s.wav".to_string()),
+            ..Default::default()
         },
         // Test 3: 30-second video with 30-second audio
         VideoSpec {
             title: "Audio Test 30s".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 30,
-            background_color: "#2ecc71".to_string(),
-            text_color: "#2c3e50".to_string(),
+            background_color: "#2ecc71".parse()?,
+            text_color: "#2c3e50".parse()?,
             font_size: 40,
             audio_track: Some("test_tone_30s.wav".to_string()),
+            ..Default::default()
         },
         // Test 4: Video shorter than audio (should clip audio)
         VideoSpec {
             title: "Audio Clipped Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 12,
-            background_color: "#9b59b6".to_string(),
-            text_color: "#f39c12".to_string(),
+            background_color: "#9b59b6".parse()?,
+            text_color: "#f39c12".parse()?,
             font_size: 52,
             audio_track: Some("test_tone_30s.wav".to_string()), // 30s audio for 12s video
+            ..Default::default()
         },
         // Test 5: Video without audio (for comparison)
         VideoSpec {
             title: "No Audio Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 15,
-            background_color: "#34495e".to_string(),
-            text_color: "#ecf0f1".to_string(),
+            background_color: "#34495e".parse()?,
+            text_color: "#ecf0f1".parse()?,
             font_size: 44,
             audio_track: None,
+            ..Default::default()
         },
         // Test 6: Test error handling with non-existent audio file
         VideoSpec {
             title: "Invalid Audio Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 10,
-            background_color: "#e67e22".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#e67e22".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 36,
             audio_track: Some("nonexistent_audio.wav".to_string()),
+            ..Default::default()
         },
     ];
 
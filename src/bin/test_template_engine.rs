@@ -17,190 +17,210 @@ async fn main() -> Result<()> {
             title: "Template Test 01".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 10,
-            background_color: "#000000".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#000000".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 32,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Template Test 02".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 15,
-            background_color: "#1a1a1a".to_string(),
-            text_color: "#e74c3c".to_string(),
+            background_color: "#1a1a1a".parse()?,
+            text_color: "#e74c3c".parse()?,
             font_size: 48,
             audio_track: None,
+            ..Default::default()
         },
         // Color variety tests
         VideoSpec {
             title: "Blue Sky Template".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 12,
-            background_color: "#3498db".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#3498db".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 56,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Green Nature".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 18,
-            background_color: "#2ecc71".to_string(),
-            text_color: "#2c3e50".to_string(),
+            background_color: "#2ecc71".parse()?,
+            text_color: "#2c3e50".parse()?,
             font_size: 40,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Purple Vibes".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 20,
-            background_color: "#9b59b6".to_string(),
-            text_color: "#f39c12".to_string(),
+            background_color: "#9b59b6".parse()?,
+            text_color: "#f39c12".parse()?,
             font_size: 64,
             audio_track: None,
+            ..Default::default()
         },
         // Font size tests
         VideoSpec {
             title: "Small Font Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 14,
-            background_color: "#34495e".to_string(),
-            text_color: "#ecf0f1".to_string(),
+            background_color: "#34495e".parse()?,
+            text_color: "#ecf0f1".parse()?,
             font_size: 24,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Medium Font".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 16,
-            background_color: "#e67e22".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#e67e22".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 44,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Large Font Style".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 22,
-            background_color: "#c0392b".to_string(),
-            text_color: "#f1c40f".to_string(),
+            background_color: "#c0392b".parse()?,
+            text_color: "#f1c40f".parse()?,
             font_size: 72,
             audio_track: None,
+            ..Default::default()
         },
         // Duration tests
         VideoSpec {
             title: "Short Duration".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 10,
-            background_color: "#16a085".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#16a085".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 50,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Long Duration Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 45,
-            background_color: "#8e44ad".to_string(),
-            text_color: "#ecf0f1".to_string(),
+            background_color: "#8e44ad".parse()?,
+            text_color: "#ecf0f1".parse()?,
             font_size: 36,
             audio_track: None,
+            ..Default::default()
         },
         // Extreme duration tests
         VideoSpec {
             title: "Max Duration".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 60,
-            background_color: "#d35400".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#d35400".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 42,
             audio_track: None,
+            ..Default::default()
         },
         // Color contrast tests
         VideoSpec {
             title: "High Contrast".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 25,
-            background_color: "#000000".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#000000".parse()?,
+            text_color: "#ffffff".parse()?,
             font_size: 58,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Low Contrast".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 30,
-            background_color: "#7f8c8d".to_string(),
-            text_color: "#95a5a6".to_string(),
+            background_color: "#7f8c8d".parse()?,
+            text_color: "#95a5a6".parse()?,
             font_size: 38,
             audio_track: None,
+            ..Default::default()
         },
         // Bright colors
         VideoSpec {
             title: "Bright Yellow".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 17,
-            background_color: "#f1c40f".to_string(),
-            text_color: "#2c3e50".to_string(),
+            background_color: "#f1c40f".parse()?,
+            text_color: "#2c3e50".parse()?,
             font_size: 46,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Neon Green".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 19,
-            background_color: "#00ff00".to_string(),
-            text_color: "#000000".to_string(),
+            background_color: "#00ff00".parse()?,
+            text_color: "#000000".parse()?,
             font_size: 52,
             audio_track: None,
+            ..Default::default()
         },
         // Pastel colors
         VideoSpec {
             title: "Soft Pink".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 23,
-            background_color: "#ffc0cb".to_string(),
-            text_color: "#8b4513".to_string(),
+            background_color: "#ffc0cb".parse()?,
+            text_color: "#8b4513".parse()?,
             font_size: 34,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "Light Blue".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 27,
-            background_color: "#87ceeb".to_string(),
-            text_color: "#191970".to_string(),
+            background_color: "#87ceeb".parse()?,
+            text_color: "#191970".parse()?,
             font_size: 60,
             audio_track: None,
+            ..Default::default()
         },
         // Edge cases
         VideoSpec {
             title: "Very Long Title That Tests Text Wrapping".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 35,
-            background_color: "#2c3e50".to_string(),
-            text_color: "#e74c3c".to_string(),
+            background_color: "#2c3e50".parse()?,
+            text_color: "#e74c3c".parse()?,
             font_size: 28,
             audio_track: None,
+            ..Default::default()
         },
         VideoSpec {
             title: "A".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 11,
-            background_color: "#ffffff".to_string(),
-            text_color: "#000000".to_string(),
+            background_color: "#ffffff".parse()?,
+            text_color: "#000000".parse()?,
             font_size: 80,
             audio_track: None,
+            ..Default::default()
         },
         // Final comprehensive test
         VideoSpec {
             title: "Final Validation".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 40,
-            background_color: "#1abc9c".to_string(),
-            text_color: "#2c3e50".to_string(),
+            background_color: "#1abc9c".parse()?,
+            text_color: "#2c3e50".parse()?,
             font_size: 54,
             audio_track: None,
+            ..Default::default()
         },
     ];
 
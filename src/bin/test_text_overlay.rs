@@ -15,10 +15,11 @@ async fn main() -> Result<()> {
         title: "Large Red Text".to_string(),
         template: VideoTemplate::SimpleText,
         duration_seconds: 5,
-        background_color: "#1a1a1a".to_string(),
-        text_color: "#e74c3c".to_string(),
+        background_color: "#1a1a1a".parse()?,
+        text_color: "#e74c3c".parse()?,
         font_size: 72,
         audio_track: None,
+        ..Default::default()
     };
 
     // Test 2: Blue text on white background
@@ -26,10 +27,11 @@ async fn main() -> Result<()> {
         title: "Blue on White".to_string(),
         template: VideoTemplate::SimpleText,
         duration_seconds: 5,
-        background_color: "#ffffff".to_string(),
-        text_color: "#3498db".to_string(),
+        background_color: "#ffffff".parse()?,
+        text_color: "#3498db".parse()?,
         font_size: 56,
         audio_track: None,
+        ..Default::default()
     };
 
     // Test 3: Green text on purple background
@@ -37,10 +39,11 @@ async fn main() -> Result<()> {
         title: "Green Purple Combo".to_string(),
         template: VideoTemplate::SimpleText,
         duration_seconds: 5,
-        background_color: "#9b59b6".to_string(),
-        text_color: "#2ecc71".to_string(),
+        background_color: "#9b59b6".parse()?,
+        text_color: "#2ecc71".parse()?,
         font_size: 48,
         audio_track: None,
+        ..Default::default()
     };
 
     let test_specs = vec![
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::video::Watermark;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub video: VideoConfig,
@@ -12,6 +14,8 @@ pub struct VideoConfig {
     pub output_directory: String,
     pub temp_directory: String,
     pub cleanup_after_upload: bool,
+    #[serde(default)]
+    pub watermark: Option<Watermark>, // brand logo for videos that don't set their own
 }
 
 #[derive(Debug, Serialize, Deserialize)]
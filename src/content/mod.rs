@@ -2,6 +2,8 @@ use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+use crate::video::Color;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentItem {
     pub id: String,
@@ -9,8 +11,8 @@ pub struct ContentItem {
     pub schedule_type: String,
     pub template: String,
     pub title: String,
-    pub background_color: String,
-    pub text_color: String,
+    pub background_color: Color,
+    pub text_color: Color,
     pub audio_file: Option<String>,
     pub tags: String,
 }
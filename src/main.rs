@@ -13,10 +13,11 @@ async fn main() -> Result<()> {
         title: "Text Overlay Demo".to_string(),
         template: VideoTemplate::SimpleText,
         duration_seconds: 8,
-        background_color: "#2c3e50".to_string(),
-        text_color: "#e74c3c".to_string(),
+        background_color: "#2c3e50".parse()?,
+        text_color: "#e74c3c".parse()?,
         font_size: 64,
         audio_track: None,
+        ..Default::default()
     };
 
     println!("Generating test video...");
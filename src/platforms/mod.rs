@@ -2,6 +2,8 @@ use async_trait::async_trait;
 use std::path::Path;
 use thiserror::Error;
 
+use crate::video::OutputProfile;
+
 #[derive(Debug)]
 pub struct VideoMetadata {
     pub title: String,
@@ -47,6 +49,12 @@ pub trait VideoPlatform {
     fn platform_name(&self) -> &str;
     fn max_file_size(&self) -> u64;
     fn supported_formats(&self) -> Vec<String>;
+
+    /// Output format videos for this platform are rendered in; its safe area
+    /// should match the platform's player, e.g. `SafeAreaPreset::TikTok`
+    fn output_profile(&self) -> OutputProfile {
+        OutputProfile::vertical()
+    }
 }
 
 // TODO: Implement real platform integrations and mock platform for testing in later phases
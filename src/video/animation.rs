@@ -0,0 +1,313 @@
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use super::encoder::{FrameSource, SourceFrame};
+
+/// Renders the picture of a video at any point in time.
+///
+/// Implement this to build animated templates and pass it to
+/// `VideoGenerator::generate_rendered_video`. Frames must match the size of
+/// the spec's `output_profile`.
+pub trait FrameRenderer {
+    /// Frame shown `time` seconds into the video, or `None` when it is
+    /// identical to the previous frame. The first call must return a frame.
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>>;
+}
+
+/// Built-in animations for the SimpleText and TitleCard templates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TitleAnimation {
+    /// Text fades in from the background
+    FadeIn { seconds: f32 },
+    /// Title appears character by character
+    Typewriter { characters_per_second: f32 },
+    /// Background slowly zooms in to `zoom` (e.g. 1.15) over the whole video
+    KenBurns { zoom: f32 },
+}
+
+impl TitleAnimation {
+    /// Check that the animation's parameters can be rendered
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            TitleAnimation::FadeIn { seconds } => {
+                if !seconds.is_finite() || seconds < 0.0 {
+                    return Err(anyhow::anyhow!(
+                        "Fade-in length must be zero or more seconds, got {}",
+                        seconds
+                    ));
+                }
+            }
+            TitleAnimation::Typewriter {
+                characters_per_second,
+            } => {
+                if !characters_per_second.is_finite() || characters_per_second <= 0.0 {
+                    return Err(anyhow::anyhow!(
+                        "Typewriter speed must be a positive number of characters per second, got {}",
+                        characters_per_second
+                    ));
+                }
+            }
+            TitleAnimation::KenBurns { zoom } => {
+                if !zoom.is_finite() || zoom < 1.0 {
+                    return Err(anyhow::anyhow!(
+                        "Ken Burns zoom must be at least 1.0, got {}",
+                        zoom
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Feeds a renderer into the encode pipeline
+pub(crate) struct RendererSource<'a>(pub(crate) &'a mut dyn FrameRenderer);
+
+impl FrameSource for RendererSource<'_> {
+    fn frame(&mut self, _index: u32, time: f64) -> Result<SourceFrame> {
+        Ok(match self.0.render(time)? {
+            Some(image) => SourceFrame::Rgb(image),
+            None => SourceFrame::Repeat,
+        })
+    }
+}
+
+/// One image for the whole video
+pub(crate) struct StillFrame {
+    image: Option<RgbImage>,
+}
+
+impl StillFrame {
+    pub(crate) fn new(image: RgbImage) -> Self {
+        Self { image: Some(image) }
+    }
+}
+
+impl FrameRenderer for StillFrame {
+    fn render(&mut self, _time: f64) -> Result<Option<RgbImage>> {
+        Ok(self.image.take())
+    }
+}
+
+/// Blend from the bare background to the finished frame
+pub(crate) struct FadeIn {
+    background: RgbImage,
+    frame: RgbImage,
+    seconds: f32,
+    finished: bool,
+}
+
+impl FadeIn {
+    pub(crate) fn new(background: RgbImage, frame: RgbImage, seconds: f32) -> Self {
+        Self {
+            background,
+            frame,
+            seconds,
+            finished: false,
+        }
+    }
+}
+
+impl FrameRenderer for FadeIn {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let progress = fade_progress(time, self.seconds);
+        self.finished = progress >= 1.0;
+        Ok(Some(blend(&self.background, &self.frame, progress)))
+    }
+}
+
+/// Reveal text one character at a time; `draw` renders a frame showing the first n characters
+pub(crate) struct Typewriter<F> {
+    characters_per_second: f32,
+    total_chars: usize,
+    shown: Option<usize>,
+    draw: F,
+}
+
+impl<F: FnMut(usize) -> Result<RgbImage>> Typewriter<F> {
+    pub(crate) fn new(characters_per_second: f32, total_chars: usize, draw: F) -> Self {
+        Self {
+            characters_per_second,
+            total_chars,
+            shown: None,
+            draw,
+        }
+    }
+}
+
+impl<F: FnMut(usize) -> Result<RgbImage>> FrameRenderer for Typewriter<F> {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let visible = typed_chars(time, self.characters_per_second, self.total_chars);
+
+        // Only redraw when another character appears
+        if self.shown == Some(visible) {
+            return Ok(None);
+        }
+        self.shown = Some(visible);
+        (self.draw)(visible).map(Some)
+    }
+}
+
+/// Slow zoom into the center of the background with a still foreground on top;
+/// `draw_foreground` paints the foreground onto each zoomed background
+pub(crate) struct KenBurns<F> {
+    background: RgbImage,
+    zoom: f32,
+    duration_seconds: f32,
+    draw_foreground: F,
+}
+
+impl<F: FnMut(&mut RgbImage) -> Result<()>> KenBurns<F> {
+    pub(crate) fn new(
+        background: RgbImage,
+        zoom: f32,
+        duration_seconds: f32,
+        draw_foreground: F,
+    ) -> Self {
+        Self {
+            background,
+            zoom,
+            duration_seconds,
+            draw_foreground,
+        }
+    }
+}
+
+impl<F: FnMut(&mut RgbImage) -> Result<()>> FrameRenderer for KenBurns<F> {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let scale = ken_burns_scale(time, self.zoom, self.duration_seconds);
+        let mut frame = zoom_center(&self.background, scale);
+        (self.draw_foreground)(&mut frame)?;
+        Ok(Some(frame))
+    }
+}
+
+/// How far a fade of `seconds` has come at `time`, 0.0 - 1.0
+pub(crate) fn fade_progress(time: f64, seconds: f32) -> f32 {
+    if seconds > 0.0 {
+        (time as f32 / seconds).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Characters a typewriter has revealed at `time`
+pub(crate) fn typed_chars(time: f64, characters_per_second: f32, total_chars: usize) -> usize {
+    ((time * characters_per_second as f64) as usize).min(total_chars)
+}
+
+/// Ken Burns zoom factor at `time`, from 1.0 at the start to `zoom` at the end
+pub(crate) fn ken_burns_scale(time: f64, zoom: f32, duration_seconds: f32) -> f32 {
+    let progress = (time as f32 / duration_seconds.max(f32::EPSILON)).clamp(0.0, 1.0);
+    1.0 + (zoom - 1.0) * progress
+}
+
+/// Linear blend between two equally sized frames, `progress` 0.0 = `from`, 1.0 = `to`
+pub(crate) fn blend(from: &RgbImage, to: &RgbImage, progress: f32) -> RgbImage {
+    let mut blended = from.clone();
+    for (out, target) in blended.pixels_mut().zip(to.pixels()) {
+        for channel in 0..3 {
+            let a = out[channel] as f32;
+            let b = target[channel] as f32;
+            out[channel] = (a + (b - a) * progress).round() as u8;
+        }
+    }
+    blended
+}
+
+/// Crop the center 1/`scale` of `image` and scale it back to full size
+pub(crate) fn zoom_center(image: &RgbImage, scale: f32) -> RgbImage {
+    let (width, height) = image.dimensions();
+    if scale <= 1.0 {
+        return image.clone();
+    }
+
+    let crop_width = ((width as f32 / scale).round() as u32).clamp(1, width);
+    let crop_height = ((height as f32 / scale).round() as u32).clamp(1, height);
+    let x = (width - crop_width) / 2;
+    let y = (height - crop_height) / 2;
+
+    let cropped = imageops::crop_imm(image, x, y, crop_width, crop_height).to_image();
+    imageops::resize(&cropped, width, height, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_fade_in_and_typewriter_progress() {
+        let black = RgbImage::from_pixel(4, 4, Rgb([0, 0, 0]));
+        let white = RgbImage::from_pixel(4, 4, Rgb([255, 255, 255]));
+
+        let mut fade = FadeIn::new(black, white, 2.0);
+        assert_eq!(fade.render(0.0).unwrap().unwrap().get_pixel(0, 0)[0], 0);
+        assert_eq!(fade.render(1.0).unwrap().unwrap().get_pixel(0, 0)[0], 128);
+        assert_eq!(fade.render(2.0).unwrap().unwrap().get_pixel(0, 0)[0], 255);
+        assert!(fade.render(2.5).unwrap().is_none());
+
+        let mut typed = Vec::new();
+        let mut typewriter = Typewriter::new(10.0, 5, |visible| {
+            typed.push(visible);
+            Ok(RgbImage::new(1, 1))
+        });
+        for frame in 0..30 {
+            typewriter.render(frame as f64 / 30.0).unwrap();
+        }
+        assert_eq!(typed, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_invalid_animation_parameters_are_rejected() {
+        assert!(TitleAnimation::FadeIn { seconds: 0.0 }.validate().is_ok());
+        assert!(TitleAnimation::FadeIn { seconds: -1.0 }.validate().is_err());
+        assert!(TitleAnimation::FadeIn { seconds: f32::NAN }
+            .validate()
+            .is_err());
+
+        let typewriter = |characters_per_second| TitleAnimation::Typewriter {
+            characters_per_second,
+        };
+        assert!(typewriter(12.0).validate().is_ok());
+        assert!(typewriter(0.0).validate().is_err());
+        assert!(typewriter(f32::INFINITY).validate().is_err());
+
+        assert!(TitleAnimation::KenBurns { zoom: 1.0 }.validate().is_ok());
+        let error = TitleAnimation::KenBurns { zoom: 0.5 }
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("0.5"), "{}", error);
+        assert!(TitleAnimation::KenBurns { zoom: f32::NAN }
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_ken_burns_zooms_into_center() {
+        // White frame with a black square covering the middle fifth
+        let background = RgbImage::from_fn(100, 100, |x, y| {
+            if (40..60).contains(&x) && (40..60).contains(&y) {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+
+        let mut ken_burns = KenBurns::new(background, 2.0, 10.0, |_: &mut RgbImage| Ok(()));
+        let start = ken_burns.render(0.0).unwrap().unwrap();
+        let end = ken_burns.render(10.0).unwrap().unwrap();
+
+        // At 2x the square grows to cover 30..70
+        assert_eq!(start.get_pixel(35, 50)[0], 255);
+        assert_eq!(end.get_pixel(35, 50)[0], 0);
+        assert_eq!(end.get_pixel(10, 50)[0], 255);
+        assert_eq!(end.dimensions(), (100, 100));
+    }
+}
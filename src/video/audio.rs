@@ -0,0 +1,482 @@
+use anyhow::Result;
+use ffmpeg_next as ffmpeg;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::loudness::{self, LoudnessReport, LoudnessTarget};
+
+use ffmpeg::format::sample::Type as SampleType;
+use ffmpeg::format::Sample;
+use ffmpeg::ChannelLayout;
+
+/// Output sample rate of every generated video
+pub(crate) const SAMPLE_RATE: u32 = 44_100;
+/// Output channel count of every generated video (stereo)
+pub(crate) const CHANNELS: usize = 2;
+
+const AUDIO_BIT_RATE: usize = 128_000; // 128 kbps AAC
+const OUTPUT_FORMAT: Sample = Sample::F32(SampleType::Planar);
+
+/// How the audio track is placed under the video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioOptions {
+    pub loop_audio: bool,          // repeat a short track until the video ends
+    pub start_offset_seconds: f32, // skip this much of the track before playing
+    pub fade_in_seconds: f32,
+    pub fade_out_seconds: f32, // ends where the audio ends, at the latest with the video
+    pub gain_db: f32,          // applied to every sample, 0.0 leaves the level unchanged
+    pub normalize: Option<LoudnessTarget>, // EBU R128 normalization, applied after gain_db
+}
+
+impl Default for AudioOptions {
+    fn default() -> Self {
+        Self {
+            loop_audio: false,
+            start_offset_seconds: 0.0,
+            fade_in_seconds: 0.0,
+            fade_out_seconds: 0.0,
+            gain_db: 0.0,
+            normalize: None,
+        }
+    }
+}
+
+/// Decoded audio as planar 44.1kHz stereo samples in the range -1.0..=1.0
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AudioBuffer {
+    pub(crate) channels: [Vec<f32>; CHANNELS],
+}
+
+impl AudioBuffer {
+    /// Number of samples per channel
+    pub(crate) fn len(&self) -> usize {
+        self.channels[0].len()
+    }
+
+    /// Trim or pad with silence so the buffer lasts exactly `samples` per channel
+    pub(crate) fn fit_to_length(&mut self, samples: usize) {
+        for channel in &mut self.channels {
+            channel.resize(samples, 0.0);
+        }
+    }
+
+    /// Loop, fade and amplify the track so it lasts exactly `samples` per channel.
+    /// Whatever the track length, the result matches the video length. The start
+    /// offset is skipped while decoding, see `decode_audio`.
+    pub(crate) fn apply_options(&mut self, options: &AudioOptions, samples: usize) -> Result<()> {
+        let seconds = [options.fade_in_seconds, options.fade_out_seconds];
+        if seconds.iter().any(|value| value.is_nan() || *value < 0.0) {
+            return Err(anyhow::anyhow!("Audio fade lengths must not be negative"));
+        }
+        if !options.gain_db.is_finite() {
+            return Err(anyhow::anyhow!("Audio gain must be a finite number of dB"));
+        }
+
+        if options.loop_audio && !self.is_empty() && self.len() < samples {
+            for channel in &mut self.channels {
+                let track = channel.clone();
+                channel.extend(track.iter().cycle().take(samples - track.len()));
+            }
+        }
+
+        // Fades follow the audible part, a short unlooped track fades before the silence
+        let audible = self.len().min(samples);
+        self.fit_to_length(samples);
+
+        let gain = 10f32.powf(options.gain_db / 20.0);
+        let fade_in = (options.fade_in_seconds * SAMPLE_RATE as f32) as usize;
+        let fade_out = (options.fade_out_seconds * SAMPLE_RATE as f32) as usize;
+
+        for channel in &mut self.channels {
+            for (index, sample) in channel[..audible].iter_mut().enumerate() {
+                let mut level = gain;
+                if index < fade_in {
+                    level *= index as f32 / fade_in as f32;
+                }
+                let remaining = audible - index;
+                if remaining <= fade_out {
+                    level *= (remaining - 1) as f32 / fade_out as f32;
+                }
+                *sample = (*sample * level).clamp(-1.0, 1.0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Normalize the loudness of the whole buffer, returning what was measured
+    pub(crate) fn normalize(&mut self, target: &LoudnessTarget) -> LoudnessReport {
+        loudness::normalize(&mut self.channels, SAMPLE_RATE, target)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append the samples of a resampled planar frame
+    fn extend_from_frame(&mut self, frame: &ffmpeg::frame::Audio) {
+        let samples = frame.samples();
+        for (index, channel) in self.channels.iter_mut().enumerate() {
+            channel.extend_from_slice(&frame.plane::<f32>(index)[..samples]);
+        }
+    }
+}
+
+/// Find an audio file either as given or relative to the bundled assets
+pub(crate) fn resolve_audio_path(audio_path: &str) -> Result<PathBuf> {
+    let audio_file_path = Path::new(audio_path);
+    if audio_file_path.exists() {
+        return Ok(audio_file_path.to_path_buf());
+    }
+
+    // Try relative path from assets
+    let assets_audio_path = Path::new("src/assets/audio").join(audio_path);
+    if assets_audio_path.exists() {
+        return Ok(assets_audio_path);
+    }
+
+    Err(anyhow::anyhow!(
+        "Audio file not found: {} (also tried {})",
+        audio_path,
+        assets_audio_path.display()
+    ))
+}
+
+/// Decode the best audio stream of `path` from `start_seconds` on and resample
+/// it to 44.1kHz stereo.
+///
+/// The input is seeked to the offset rather than decoded up to it, and reading
+/// stops once `samples` per channel are buffered; only a track that ends
+/// sooner is decoded to its end. `None` decodes the rest of the track.
+pub(crate) fn decode_audio(
+    path: &Path,
+    start_seconds: f32,
+    samples: Option<usize>,
+) -> Result<AudioBuffer> {
+    if start_seconds.is_nan() || start_seconds < 0.0 {
+        return Err(anyhow::anyhow!("Audio start offset must not be negative"));
+    }
+
+    let mut input = ffmpeg::format::input(&path)?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Audio)
+        .ok_or_else(|| anyhow::anyhow!("No audio stream found in {}", path.display()))?;
+    let stream_index = stream.index();
+    let time_base = f64::from(stream.time_base());
+    let origin = match stream.start_time() {
+        i64::MIN => 0.0, // AV_NOPTS_VALUE
+        start => start as f64 * time_base,
+    };
+
+    let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context.decoder().audio()?;
+
+    // Seeking lands on a packet at or before the offset; what is decoded
+    // ahead of the offset is dropped at the end
+    if start_seconds > 0.0 {
+        let position = ((origin + start_seconds as f64) * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        input.seek(position, ..position)?;
+    }
+    let frame_time =
+        |frame: &ffmpeg::frame::Audio| frame.timestamp().unwrap_or(0) as f64 * time_base - origin;
+
+    let mut buffer = AudioBuffer::default();
+    let mut resampler = None;
+    let mut decoded = ffmpeg::frame::Audio::empty();
+    let mut first_time = None; // track time of the first decoded sample
+    let skip = |first_time: Option<f64>| match first_time {
+        Some(time) if start_seconds > 0.0 => ((start_seconds as f64 - time) * SAMPLE_RATE as f64)
+            .round()
+            .max(0.0) as usize,
+        _ => 0,
+    };
+
+    let mut complete = false;
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            first_time.get_or_insert_with(|| frame_time(&decoded));
+            resample_frame(&mut resampler, &mut decoded, &mut buffer)?;
+        }
+
+        if samples.is_some_and(|samples| buffer.len() >= skip(first_time) + samples) {
+            complete = true;
+            break;
+        }
+    }
+
+    if !complete {
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            first_time.get_or_insert_with(|| frame_time(&decoded));
+            resample_frame(&mut resampler, &mut decoded, &mut buffer)?;
+        }
+
+        // Drain the samples still buffered inside the resampler
+        if let Some(resampler) = resampler.as_mut() {
+            loop {
+                let mut resampled =
+                    ffmpeg::frame::Audio::new(OUTPUT_FORMAT, 4096, ChannelLayout::STEREO);
+                resampler.flush(&mut resampled)?;
+                if resampled.samples() == 0 {
+                    break;
+                }
+                buffer.extend_from_frame(&resampled);
+            }
+        }
+    }
+
+    let skip = skip(first_time);
+    if start_seconds > 0.0 && skip >= buffer.len() {
+        return Err(anyhow::anyhow!(
+            "Audio start offset {}s is beyond the end of the track",
+            start_seconds
+        ));
+    }
+    for channel in &mut buffer.channels {
+        channel.drain(..skip);
+        if let Some(samples) = samples {
+            channel.truncate(samples);
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Convert one decoded frame, creating the resampler from the first frame's format
+fn resample_frame(
+    resampler: &mut Option<ffmpeg::software::resampling::Context>,
+    decoded: &mut ffmpeg::frame::Audio,
+    buffer: &mut AudioBuffer,
+) -> Result<()> {
+    // Some decoders (e.g. WAV) only report a channel count, not a layout
+    if decoded.channel_layout().is_empty() {
+        decoded.set_channel_layout(ChannelLayout::default(decoded.channels() as i32));
+    }
+
+    if resampler.is_none() {
+        *resampler = Some(ffmpeg::software::resampling::Context::get(
+            decoded.format(),
+            decoded.channel_layout(),
+            decoded.rate(),
+            OUTPUT_FORMAT,
+            ChannelLayout::STEREO,
+            SAMPLE_RATE,
+        )?);
+    }
+    let Some(resampler) = resampler.as_mut() else {
+        return Ok(());
+    };
+
+    // Leave headroom for upsampling plus whatever the resampler buffered earlier
+    let capacity = decoded.samples() * SAMPLE_RATE as usize / decoded.rate().max(1) as usize + 256;
+    let mut resampled = ffmpeg::frame::Audio::new(OUTPUT_FORMAT, capacity, ChannelLayout::STEREO);
+    resampler.run(decoded, &mut resampled)?;
+    buffer.extend_from_frame(&resampled);
+
+    Ok(())
+}
+
+/// AAC encoder feeding an audio stream of an output that is shared with the video
+pub(crate) struct AudioEncoder {
+    encoder: ffmpeg::encoder::Audio,
+    stream_index: usize,
+    frame_size: usize,
+    stream_time_base: ffmpeg::Rational,
+    position: usize, // next sample of the buffer to encode
+}
+
+impl AudioEncoder {
+    /// Add an AAC stream to `output`; must be called before the header is written
+    pub(crate) fn add_stream(
+        output: &mut ffmpeg::format::context::Output,
+        global_header: bool,
+    ) -> Result<Self> {
+        let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::AAC)
+            .ok_or_else(|| anyhow::anyhow!("AAC encoder not available"))?;
+
+        let mut audio_stream = output.add_stream(codec)?;
+        let audio_context =
+            ffmpeg::codec::context::Context::from_parameters(audio_stream.parameters())?;
+        let mut audio_encoder = audio_context.encoder().audio()?;
+
+        audio_encoder.set_rate(SAMPLE_RATE as i32);
+        audio_encoder.set_channel_layout(ChannelLayout::STEREO);
+        audio_encoder.set_format(OUTPUT_FORMAT);
+        audio_encoder.set_bit_rate(AUDIO_BIT_RATE);
+        audio_encoder.set_time_base((1, SAMPLE_RATE as i32));
+
+        if global_header {
+            audio_encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = audio_encoder.open_as(codec)?;
+        audio_stream.set_parameters(&encoder);
+        audio_stream.set_time_base((1, SAMPLE_RATE as i32));
+
+        // Encoders without a fixed frame size accept any chunk length
+        let frame_size = match encoder.frame_size() {
+            0 => 1024,
+            size => size as usize,
+        };
+
+        Ok(Self {
+            encoder,
+            stream_index: audio_stream.index(),
+            frame_size,
+            stream_time_base: (1, SAMPLE_RATE as i32).into(),
+            position: 0,
+        })
+    }
+
+    /// Read back the time base the muxer chose while writing the header
+    pub(crate) fn header_written(
+        &mut self,
+        output: &ffmpeg::format::context::Output,
+    ) -> Result<()> {
+        self.stream_time_base = output
+            .stream(self.stream_index)
+            .ok_or_else(|| anyhow::anyhow!("Audio stream missing after header"))?
+            .time_base();
+        Ok(())
+    }
+
+    /// Encode every complete frame of `buffer` that starts before `until` seconds
+    pub(crate) fn encode_until(
+        &mut self,
+        buffer: &AudioBuffer,
+        until: f64,
+        output: &mut ffmpeg::format::context::Output,
+    ) -> Result<()> {
+        let end = ((until * SAMPLE_RATE as f64) as usize).min(buffer.len());
+        while self.position + self.frame_size <= end {
+            self.encode_chunk(buffer, self.frame_size, output)?;
+        }
+        Ok(())
+    }
+
+    /// Encode the rest of `buffer`, including a short final frame, and flush the encoder
+    pub(crate) fn finish(
+        &mut self,
+        buffer: &AudioBuffer,
+        output: &mut ffmpeg::format::context::Output,
+    ) -> Result<()> {
+        while self.position < buffer.len() {
+            let samples = self.frame_size.min(buffer.len() - self.position);
+            self.encode_chunk(buffer, samples, output)?;
+        }
+
+        self.encoder.send_eof()?;
+        self.write_packets(output)
+    }
+
+    fn encode_chunk(
+        &mut self,
+        buffer: &AudioBuffer,
+        samples: usize,
+        output: &mut ffmpeg::format::context::Output,
+    ) -> Result<()> {
+        let mut frame = ffmpeg::frame::Audio::new(OUTPUT_FORMAT, samples, ChannelLayout::STEREO);
+        frame.set_rate(SAMPLE_RATE);
+        for (index, channel) in buffer.channels.iter().enumerate() {
+            frame.plane_mut::<f32>(index)[..samples]
+                .copy_from_slice(&channel[self.position..self.position + samples]);
+        }
+        // The encoder time base is one sample, so the pts is the sample position
+        frame.set_pts(Some(self.position as i64));
+        self.position += samples;
+
+        self.encoder.send_frame(&frame)?;
+        self.write_packets(output)
+    }
+
+    fn write_packets(&mut self, output: &mut ffmpeg::format::context::Output) -> Result<()> {
+        let encoder_time_base = self.encoder.time_base();
+        let mut encoded = ffmpeg::packet::Packet::empty();
+        while self.encoder.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(self.stream_index);
+            encoded.rescale_ts(encoder_time_base, self.stream_time_base);
+            encoded.write_interleaved(output)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_to_length_pads_and_trims() {
+        let mut buffer = AudioBuffer {
+            channels: [vec![0.5; 10], vec![-0.5; 10]],
+        };
+
+        buffer.fit_to_length(15);
+        assert_eq!(buffer.len(), 15);
+        assert_eq!(buffer.channels[1][9], -0.5);
+        assert_eq!(buffer.channels[1][14], 0.0);
+
+        buffer.fit_to_length(4);
+        assert_eq!(buffer.channels[0], vec![0.5; 4]);
+        assert_eq!(buffer.channels[1].len(), 4);
+    }
+
+    #[test]
+    fn test_apply_options_loops_and_fades() {
+        // One second ramp, so every sample identifies its position in the track
+        let ramp: Vec<f32> = (0..SAMPLE_RATE)
+            .map(|i| i as f32 / SAMPLE_RATE as f32)
+            .collect();
+        let mut buffer = AudioBuffer {
+            channels: [ramp.clone(), ramp],
+        };
+        let options = AudioOptions {
+            loop_audio: true,
+            fade_out_seconds: 0.25,
+            ..Default::default()
+        };
+
+        let total = SAMPLE_RATE as usize * 2;
+        buffer.apply_options(&options, total).unwrap();
+
+        assert_eq!(buffer.len(), total);
+        // Loops back to the start of the ramp after one second
+        assert_eq!(buffer.channels[0][0], 0.0);
+        assert_eq!(
+            buffer.channels[0][SAMPLE_RATE as usize + 100],
+            100.0 / SAMPLE_RATE as f32
+        );
+        // Fade-out reaches silence on the last sample
+        assert_eq!(buffer.channels[1][total - 1], 0.0);
+    }
+
+    #[test]
+    fn test_decode_starts_at_offset_and_stops_at_budget() {
+        ffmpeg::init().unwrap();
+        let path = resolve_audio_path("test_tone_10s.wav").unwrap();
+
+        let whole = decode_audio(&path, 0.0, None).unwrap();
+        let rest = decode_audio(&path, 4.0, None).unwrap();
+        let excerpt = decode_audio(&path, 4.0, Some(SAMPLE_RATE as usize * 2)).unwrap();
+
+        let offset = SAMPLE_RATE as usize * 4;
+        assert!(rest.len().abs_diff(whole.len() - offset) < 64);
+        assert_eq!(excerpt.len(), SAMPLE_RATE as usize * 2);
+        // The excerpt lines up with the same stretch of the whole track
+        let error = excerpt.channels[0]
+            .iter()
+            .zip(&whole.channels[0][offset..])
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(error < 0.01, "{}", error);
+
+        assert!(decode_audio(&path, 20.0, Some(SAMPLE_RATE as usize)).is_err());
+    }
+}
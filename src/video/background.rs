@@ -0,0 +1,282 @@
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::color::Color;
+use super::footage::{BackgroundVideo, Footage};
+
+/// What fills the frame behind the text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Background {
+    Solid {
+        color: Color,
+    },
+    /// CSS-style angle: 0 runs bottom to top, 90 left to right, 180 top to bottom
+    LinearGradient {
+        angle_degrees: f32,
+        stops: Vec<GradientStop>,
+    },
+    /// Circles around the center (fractions of the frame size), reaching the
+    /// last stop at the farthest corner
+    RadialGradient {
+        #[serde(default = "half")]
+        center_x: f32,
+        #[serde(default = "half")]
+        center_y: f32,
+        stops: Vec<GradientStop>,
+    },
+    /// Image file scaled to the frame; contain letterboxes on `VideoSpec::background_color`
+    Image {
+        path: String,
+        #[serde(default)]
+        fit: ImageFit,
+        #[serde(default)]
+        blur: f32, // gaussian sigma in px, 0 = sharp
+        #[serde(default)]
+        dim: f32, // 0.0 - 1.0 towards black, for legible text on busy artwork
+    },
+    /// Footage playing behind SimpleText and TitleCard; other templates show
+    /// its first frame as a still
+    Video(BackgroundVideo),
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+/// A color at `position` along the gradient, 0.0 = start, 1.0 = end
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: Color,
+}
+
+/// How an image is scaled to the frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageFit {
+    #[default]
+    Cover, // fill the frame, cropping the overflow
+    Contain, // show the whole image, letterboxed
+}
+
+impl Background {
+    /// Render the background at `width` x `height`; `base` shows through
+    /// letterboxing and transparent colors
+    pub(crate) fn render(&self, width: u32, height: u32, base: Color) -> Result<RgbImage> {
+        let mut image = RgbImage::from_pixel(width, height, base.to_rgb());
+
+        match self {
+            Background::Solid { color } => {
+                for pixel in image.pixels_mut() {
+                    color.blend(pixel, 1.0);
+                }
+            }
+            Background::LinearGradient {
+                angle_degrees,
+                stops,
+            } => {
+                let stops = sorted_stops(stops)?;
+
+                // Project every pixel onto the gradient line through the center;
+                // its length makes the corners land exactly on 0.0 and 1.0
+                let angle = angle_degrees.to_radians();
+                let (dx, dy) = (angle.sin(), -angle.cos());
+                let length = (width as f32 * dx).abs() + (height as f32 * dy).abs();
+                let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+                for (x, y, pixel) in image.enumerate_pixels_mut() {
+                    let along = (x as f32 + 0.5 - cx) * dx + (y as f32 + 0.5 - cy) * dy;
+                    let position = along / length.max(f32::EPSILON) + 0.5;
+                    gradient_color(&stops, position).blend(pixel, 1.0);
+                }
+            }
+            Background::RadialGradient {
+                center_x,
+                center_y,
+                stops,
+            } => {
+                let stops = sorted_stops(stops)?;
+
+                let (cx, cy) = (center_x * width as f32, center_y * height as f32);
+                let (w, h) = (width as f32, height as f32);
+                let radius = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)]
+                    .into_iter()
+                    .map(|(x, y)| ((x - cx).powi(2) + (y - cy).powi(2)).sqrt())
+                    .fold(0.0, f32::max);
+
+                for (x, y, pixel) in image.enumerate_pixels_mut() {
+                    let distance =
+                        ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+                    let position = distance / radius.max(f32::EPSILON);
+                    gradient_color(&stops, position).blend(pixel, 1.0);
+                }
+            }
+            Background::Image {
+                path,
+                fit,
+                blur,
+                dim,
+            } => {
+                let source = image::open(Path::new(path))
+                    .map_err(|e| {
+                        anyhow::anyhow!("Failed to load background image {}: {}", path, e)
+                    })?
+                    .to_rgb8();
+                place_image(&mut image, &source, *fit);
+
+                if *blur > 0.0 {
+                    image = imageops::fast_blur(&image, *blur);
+                }
+                if *dim > 0.0 {
+                    for pixel in image.pixels_mut() {
+                        Color::BLACK.blend(pixel, dim.clamp(0.0, 1.0));
+                    }
+                }
+            }
+            Background::Video(video) => {
+                image = Footage::open(video, width, height)?.frame_at(0.0)?;
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// Scale `source` into `frame` with cover or contain semantics, centered
+pub(crate) fn place_image(frame: &mut RgbImage, source: &RgbImage, fit: ImageFit) {
+    let (width, height) = frame.dimensions();
+    let scale_x = width as f32 / source.width() as f32;
+    let scale_y = height as f32 / source.height() as f32;
+    let scale = match fit {
+        ImageFit::Cover => scale_x.max(scale_y),
+        ImageFit::Contain => scale_x.min(scale_y),
+    };
+
+    let scaled_width = ((source.width() as f32 * scale).round() as u32).max(1);
+    let scaled_height = ((source.height() as f32 * scale).round() as u32).max(1);
+    let scaled = imageops::resize(source, scaled_width, scaled_height, FilterType::Triangle);
+
+    // Negative offsets crop the overflow of a covering image
+    imageops::overlay(
+        frame,
+        &scaled,
+        (width as i64 - scaled_width as i64) / 2,
+        (height as i64 - scaled_height as i64) / 2,
+    );
+}
+
+/// Stops ordered by position, at least one
+fn sorted_stops(stops: &[GradientStop]) -> Result<Vec<GradientStop>> {
+    if stops.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Gradient background needs at least one stop"
+        ));
+    }
+    let mut sorted = stops.to_vec();
+    sorted.sort_by(|a, b| a.position.total_cmp(&b.position));
+    Ok(sorted)
+}
+
+/// Color at `position`, interpolated between the surrounding sorted stops
+fn gradient_color(sorted: &[GradientStop], position: f32) -> Color {
+    let first = sorted[0];
+    let last = sorted[sorted.len() - 1];
+    if position <= first.position {
+        return first.color;
+    }
+    if position >= last.position {
+        return last.color;
+    }
+
+    let window = sorted
+        .windows(2)
+        .find(|pair| position <= pair[1].position)
+        .unwrap_or(&sorted[sorted.len() - 2..]);
+    let (from, to) = (window[0], window[1]);
+    let t = (position - from.position) / (to.position - from.position).max(f32::EPSILON);
+
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::rgba(
+        mix(from.color.r, to.color.r),
+        mix(from.color.g, to.color.g),
+        mix(from.color.b, to.color.b),
+        mix(from.color.a, to.color.a),
+    )
+}
+
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Background::Solid { color }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn stops(from: Color, to: Color) -> Vec<GradientStop> {
+        vec![
+            GradientStop {
+                position: 0.0,
+                color: from,
+            },
+            GradientStop {
+                position: 1.0,
+                color: to,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_gradients_run_between_stops() {
+        let vertical = Background::LinearGradient {
+            angle_degrees: 180.0,
+            stops: stops(Color::BLACK, Color::WHITE),
+        };
+        let image = vertical.render(10, 100, Color::BLACK).unwrap();
+        assert!(image.get_pixel(5, 0)[0] < 5);
+        assert!((image.get_pixel(5, 50)[0] as i32 - 128).abs() < 5);
+        assert!(image.get_pixel(5, 99)[0] > 250);
+        assert_eq!(image.get_pixel(0, 30), image.get_pixel(9, 30));
+
+        let radial = Background::RadialGradient {
+            center_x: 0.5,
+            center_y: 0.5,
+            stops: stops(Color::WHITE, Color::BLACK),
+        };
+        let image = radial.render(100, 100, Color::BLACK).unwrap();
+        assert!(image.get_pixel(50, 50)[0] > 250);
+        assert!(image.get_pixel(0, 0)[0] < 5);
+
+        let empty = Background::LinearGradient {
+            angle_degrees: 90.0,
+            stops: Vec::new(),
+        };
+        assert!(empty.render(10, 10, Color::BLACK).is_err());
+    }
+
+    #[test]
+    fn test_cover_crops_and_contain_letterboxes() {
+        // Wide image: red left half, blue right half
+        let source = RgbImage::from_fn(200, 100, |x, _| {
+            if x < 100 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+
+        let mut cover = RgbImage::new(100, 100);
+        place_image(&mut cover, &source, ImageFit::Cover);
+        assert_eq!(*cover.get_pixel(10, 0), Rgb([255, 0, 0]));
+        assert_eq!(*cover.get_pixel(90, 99), Rgb([0, 0, 255]));
+
+        let mut contain = RgbImage::from_pixel(100, 100, Rgb([0, 255, 0]));
+        place_image(&mut contain, &source, ImageFit::Contain);
+        assert_eq!(*contain.get_pixel(50, 5), Rgb([0, 255, 0]));
+        assert_eq!(*contain.get_pixel(10, 50), Rgb([255, 0, 0]));
+    }
+}
@@ -0,0 +1,482 @@
+use anyhow::{Context, Result};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+use super::color::Color;
+use super::fonts::FontChain;
+use super::karaoke::{self, KaraokeStyle, Transcript};
+use super::layout::TextBox;
+use super::overlays::FrameOverlay;
+use super::profile::OutputProfile;
+use super::text::{self, CaptionBox, Highlight, TextLayout, TextLayoutOptions, TextStyle};
+
+/// Subtitle file burned into the video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Captions {
+    /// .srt or .vtt cues, or a .json `Transcript` for word-by-word karaoke captions
+    pub path: String,
+    #[serde(default)]
+    pub style: CaptionStyle,
+    #[serde(default)]
+    pub karaoke: KaraokeStyle, // transcripts only
+}
+
+/// How and where caption cues are drawn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionStyle {
+    pub font_size: u32, // shrinks when a cue would cover more than half the safe area
+    pub color: Color,
+    pub text_style: TextStyle, // caption box behind the lines by default
+    pub position: CaptionPosition,
+    pub margin: u32, // px between the text and the edge of the safe area
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 44,
+            color: Color::WHITE,
+            text_style: TextStyle {
+                caption_box: Some(CaptionBox::default()),
+                ..Default::default()
+            },
+            position: CaptionPosition::Bottom,
+            margin: 24,
+        }
+    }
+}
+
+/// Vertical placement of captions inside the profile's safe area
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptionPosition {
+    Top,
+    Center,
+    #[default]
+    Bottom,
+}
+
+/// Text shown from `start` until `end`, in seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String, // markup stripped, lines separated by '\n'
+}
+
+impl Captions {
+    /// Whether `path` is a word-level transcript rather than a subtitle file
+    pub fn is_transcript(&self) -> bool {
+        Path::new(&self.path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+    }
+
+    /// Read and parse the subtitle file, WebVTT when it has a .vtt extension
+    /// or a WEBVTT header and SRT otherwise
+    pub fn load_cues(&self) -> Result<Vec<Cue>> {
+        let input = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read captions {}", self.path))?;
+        let is_vtt = Path::new(&self.path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("vtt"))
+            || input.trim_start_matches('\u{feff}').starts_with("WEBVTT");
+
+        let cues = if is_vtt {
+            parse_webvtt(&input)
+        } else {
+            parse_srt(&input)
+        };
+        cues.with_context(|| format!("Failed to parse captions {}", self.path))
+    }
+}
+
+/// Parse SubRip cues: optional counter, `00:00:01,000 --> 00:00:03,500`, text lines
+pub fn parse_srt(input: &str) -> Result<Vec<Cue>> {
+    parse_blocks(input, false)
+}
+
+/// Parse WebVTT cues; NOTE, STYLE and REGION blocks and cue settings are ignored
+pub fn parse_webvtt(input: &str) -> Result<Vec<Cue>> {
+    parse_blocks(input, true)
+}
+
+/// Both formats are blank-line separated blocks around a `start --> end` line
+fn parse_blocks(input: &str, webvtt: bool) -> Result<Vec<Cue>> {
+    let input = input
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+
+    let mut blocks = Vec::new();
+    let mut block = Vec::new();
+    for line in input.lines() {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+        } else {
+            block.push(line);
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+
+    let mut blocks = blocks.into_iter();
+    if webvtt {
+        let header = blocks.next().unwrap_or_default();
+        if !header
+            .first()
+            .is_some_and(|line| line.starts_with("WEBVTT"))
+        {
+            return Err(anyhow::anyhow!("WebVTT file does not start with WEBVTT"));
+        }
+    }
+
+    let mut cues = Vec::new();
+    for block in blocks {
+        if webvtt
+            && ["NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|keyword| block[0].starts_with(keyword))
+        {
+            continue;
+        }
+
+        // The timing line may follow a counter or cue identifier
+        let timing = block
+            .iter()
+            .position(|line| line.contains("-->"))
+            .ok_or_else(|| anyhow::anyhow!("Cue without a timing line: {:?}", block[0]))?;
+        let (start, rest) = block[timing].split_once("-->").unwrap_or_default();
+        let end = rest.split_whitespace().next().unwrap_or_default(); // WebVTT settings follow
+        let (start, end) = (parse_timestamp(start)?, parse_timestamp(end)?);
+        if end <= start {
+            return Err(anyhow::anyhow!(
+                "Cue ends before it starts: {}",
+                block[timing]
+            ));
+        }
+
+        let text = block[timing + 1..]
+            .iter()
+            .map(|line| strip_markup(line.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.trim().is_empty() {
+            cues.push(Cue { start, end, text });
+        }
+    }
+
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+/// `hh:mm:ss,mmm`, `hh:mm:ss.mmm` or `mm:ss.mmm` in seconds
+fn parse_timestamp(timestamp: &str) -> Result<f64> {
+    let timestamp = timestamp.trim();
+    let invalid = || anyhow::anyhow!("Invalid caption timestamp '{}'", timestamp);
+
+    let parts = timestamp.split(':').collect::<Vec<_>>();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(invalid());
+    }
+    let seconds: f64 = parts[parts.len() - 1]
+        .replace(',', ".")
+        .parse()
+        .map_err(|_| invalid())?;
+    let mut total = seconds;
+    for (part, unit) in parts[..parts.len() - 1].iter().rev().zip([60.0, 3600.0]) {
+        let value: u32 = part.parse().map_err(|_| invalid())?;
+        total += value as f64 * unit;
+    }
+    Ok(total)
+}
+
+/// Drop `<i>`/`<v Name>`-style tags and `{\an8}` overrides and decode the
+/// entities WebVTT requires
+fn strip_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') if chars.peek() == Some(&'\\') => closing = Some('}'),
+            (Some(close), c) if c == close => closing = None,
+            (Some(_), _) => {}
+            (None, c) => text.push(c),
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lrm;", "")
+        .replace("&rlm;", "")
+        .replace("&amp;", "&")
+}
+
+/// Cues laid out once for the output frame
+pub(crate) struct CaptionTrack {
+    cues: Vec<Cue>,
+    layouts: Vec<TextLayout>,
+    words: Vec<Vec<(f64, Range<usize>)>>, // per cue: start and layout characters, karaoke only
+    karaoke: Option<KaraokeStyle>,
+    fonts: FontChain,
+    style: CaptionStyle,
+    center_x: f32,
+    area: (f32, f32), // top and bottom of the safe area in px
+}
+
+impl CaptionTrack {
+    /// Cues of a subtitle file, or phrases of a transcript with their word timings
+    pub(crate) fn load(
+        captions: &Captions,
+        fonts: FontChain,
+        profile: &OutputProfile,
+    ) -> Result<Self> {
+        if !captions.is_transcript() {
+            let cues = captions.load_cues()?;
+            return Ok(Self::new(cues, captions.style.clone(), fonts, profile));
+        }
+
+        let phrases = Transcript::load(&captions.path)?.phrases(&captions.karaoke);
+        let (cues, words): (Vec<_>, Vec<_>) = phrases
+            .into_iter()
+            .map(|phrase| (phrase.cue, phrase.words))
+            .unzip();
+        let mut track = Self::new(cues, captions.style.clone(), fonts, profile);
+
+        track.words = track
+            .layouts
+            .iter()
+            .zip(&track.cues)
+            .zip(words)
+            .map(|((layout, cue), words)| {
+                let ranges = words
+                    .iter()
+                    .map(|(_, range)| range.clone())
+                    .collect::<Vec<_>>();
+                let mapped = karaoke::layout_ranges(layout, &cue.text, &ranges);
+                words
+                    .into_iter()
+                    .map(|(start, _)| start)
+                    .zip(mapped)
+                    .collect()
+            })
+            .collect();
+        track.karaoke = Some(captions.karaoke.clone());
+        Ok(track)
+    }
+
+    /// Wrap every cue to the safe area of `profile`
+    pub(crate) fn new(
+        cues: Vec<Cue>,
+        style: CaptionStyle,
+        fonts: FontChain,
+        profile: &OutputProfile,
+    ) -> Self {
+        let (left, top, right, bottom) = profile.safe_area.bounds(profile.width, profile.height);
+        let margin = style.margin as f32;
+        let max_width = (right - left - margin * 2.0).max(1.0);
+        let max_height = (bottom - top) / 2.0;
+
+        let options = TextLayoutOptions {
+            margin: 0,
+            shrink_to_fit: true,
+            ..Default::default()
+        };
+        let layouts = cues
+            .iter()
+            .map(|cue| {
+                text::layout_text(
+                    &fonts,
+                    &cue.text,
+                    style.font_size as f32,
+                    max_width,
+                    max_height,
+                    &options,
+                )
+            })
+            .collect();
+
+        Self {
+            cues,
+            layouts,
+            words: Vec::new(),
+            karaoke: None,
+            fonts,
+            style,
+            center_x: (left + right) / 2.0,
+            area: (top, bottom),
+        }
+    }
+
+    /// Cue shown at `time`, of overlapping cues the one that started last,
+    /// and for karaoke the word being spoken; a word stays lit until the next one starts
+    fn active(&self, time: f64) -> Option<(usize, Option<usize>)> {
+        let cue = self
+            .cues
+            .iter()
+            .rposition(|cue| cue.start <= time && time < cue.end)?;
+        let word = self
+            .words
+            .get(cue)
+            .and_then(|words| words.iter().rposition(|(start, _)| *start <= time));
+        Some((cue, word))
+    }
+
+    /// Top edge of cue `index` in the frame
+    fn top(&self, index: usize) -> f32 {
+        let height = self.layouts[index].height();
+        let margin = self.style.margin as f32;
+        let (top, bottom) = self.area;
+        match self.style.position {
+            CaptionPosition::Top => top + margin,
+            CaptionPosition::Center => (top + bottom - height) / 2.0,
+            CaptionPosition::Bottom => bottom - margin - height,
+        }
+    }
+
+    /// Where every cue is drawn, labelled with its number and time range
+    pub(crate) fn text_boxes(&self) -> Vec<TextBox> {
+        self.cues
+            .iter()
+            .enumerate()
+            .map(|(index, cue)| {
+                let label = format!(
+                    "caption {} ({:.2}s - {:.2}s)",
+                    index + 1,
+                    cue.start,
+                    cue.end
+                );
+                TextBox::around(label, &self.layouts[index], self.center_x, self.top(index))
+            })
+            .collect()
+    }
+
+    fn draw_cue(&self, image: &mut RgbImage, (index, word): (usize, Option<usize>)) {
+        let layout = &self.layouts[index];
+        let y = self.top(index);
+
+        let (Some(karaoke), Some(word)) = (&self.karaoke, word) else {
+            text::draw_styled_layout_prefix(
+                image,
+                &self.fonts,
+                layout,
+                self.style.color,
+                &self.style.text_style,
+                self.center_x,
+                y,
+                usize::MAX,
+            );
+            return;
+        };
+
+        let mut dimmed = self.style.color;
+        dimmed.a = (dimmed.a as f32 * karaoke.dim_opacity.clamp(0.0, 1.0)).round() as u8;
+        let highlight = Highlight {
+            range: self.words[index][word].1.clone(),
+            color: karaoke.highlight_color,
+            scale: karaoke.highlight_scale,
+        };
+        text::draw_highlighted_layout(
+            image,
+            &self.fonts,
+            layout,
+            dimmed,
+            &self.style.text_style,
+            self.center_x,
+            y,
+            &highlight,
+        );
+    }
+}
+
+impl FrameOverlay for CaptionTrack {
+    fn state(&self, time: f64) -> u64 {
+        match self.active(time) {
+            None => 0,
+            Some((cue, word)) => ((cue as u64 + 1) << 32) | word.map_or(0, |word| word as u64 + 1),
+        }
+    }
+
+    fn draw(&self, image: &mut RgbImage, time: f64) {
+        if let Some(active) = self.active(time) {
+            self.draw_cue(image, active);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_srt_and_webvtt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i> there\r\n\r\n\
+                   2\r\n00:01:02,250 --> 00:01:04,000\r\nSecond {\\an8}line\r\nwraps\r\n";
+        let cues = parse_srt(srt).unwrap();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].start, 1.0);
+        assert_eq!(cues[0].end, 2.5);
+        assert_eq!(cues[0].text, "Hello there");
+        assert_eq!(cues[1].start, 62.25);
+        assert_eq!(cues[1].text, "Second line\nwraps");
+
+        let vtt = "WEBVTT - demo\n\nNOTE written by hand\n\n\
+                   intro\n00:01.000 --> 00:03.000 align:start line:0\n<v Ana>Fish &amp; chips\n\n\
+                   01:00:00.000 --> 01:00:01.000\nLate\n";
+        let cues = parse_webvtt(vtt).unwrap();
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 3.0));
+        assert_eq!(cues[0].text, "Fish & chips");
+        assert_eq!(cues[1].start, 3600.0);
+
+        assert!(parse_webvtt("1\n00:01.000 --> 00:02.000\nNo header\n").is_err());
+        assert!(parse_srt("1\n00:00:05,000 --> 00:00:04,000\nBackwards\n").is_err());
+        assert!(parse_srt("1\nnot a timing line\n").is_err());
+    }
+
+    #[test]
+    fn test_captions_follow_cues_inside_safe_area() {
+        let profile = OutputProfile {
+            width: 200,
+            height: 400,
+            ..OutputProfile::vertical()
+        };
+        let cues =
+            parse_srt("1\n00:00:01,000 --> 00:00:02,000\nA caption long enough to wrap\n").unwrap();
+        let style = CaptionStyle {
+            font_size: 20,
+            ..Default::default()
+        };
+        let track = CaptionTrack::new(cues, style, FontChain::default(), &profile);
+        assert!(track.layouts[0].lines.len() > 1);
+
+        let frame = |time| {
+            let mut image = RgbImage::new(200, 400);
+            track.draw(&mut image, time);
+            image
+        };
+        assert_ne!(track.state(0.5), track.state(1.5));
+
+        assert!(frame(0.5).pixels().all(|p| p[0] == 0));
+        let captioned = frame(1.5);
+        let (left, top, right, bottom) = profile.safe_area.bounds(200, 400);
+        let drawn = captioned
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[0] > 0)
+            .collect::<Vec<_>>();
+        assert!(!drawn.is_empty());
+        assert!(drawn.iter().all(|(x, y, _)| {
+            (*x as f32) >= left && (*x as f32) < right && (*y as f32) >= top && (*y as f32) < bottom
+        }));
+        // Lowest text sits in the lower half of the safe area
+        assert!(drawn.iter().map(|(_, y, _)| *y).max().unwrap() as f32 > (top + bottom) / 2.0);
+    }
+}
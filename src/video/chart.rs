@@ -0,0 +1,814 @@
+use anyhow::Result;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use super::animation::FrameRenderer;
+use super::color::Color;
+use super::fonts::FontChain;
+use super::layout::TextBox;
+use super::text;
+
+/// Share of the video the chart takes to grow; the rest holds the final values
+const GROW: f64 = 0.85;
+
+/// Most bars a bar race shows at once
+const RACE_BARS: usize = 10;
+
+/// How the Chart template draws its data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChartKind {
+    /// A group of vertical bars per row, growing from zero one row after another
+    #[default]
+    Bar,
+    /// A horizontal bar per series, re-ranked as the rows play back in order
+    BarRace,
+    /// A line per series, drawn left to right through the rows
+    Line,
+}
+
+/// Values of a chart: a label per row and one value per row in every series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChartData {
+    /// CSV file with a header row; the first column labels the rows and every
+    /// other column is a series named by its header
+    Csv { path: String },
+    /// The same table written out in the spec
+    Series {
+        labels: Vec<String>,
+        series: Vec<ChartSeries>,
+    },
+}
+
+/// One named column of a chart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartSeries {
+    pub name: String,
+    pub values: Vec<f64>, // one per row label
+}
+
+/// Row labels and series checked to line up
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChartTable {
+    labels: Vec<String>,
+    series: Vec<ChartSeries>,
+}
+
+impl ChartData {
+    /// Read the CSV file or check the inline series
+    pub(crate) fn load(&self) -> Result<ChartTable> {
+        match self {
+            ChartData::Csv { path } => {
+                let file = std::fs::File::open(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to open chart data {}: {}", path, e))?;
+                parse_csv(file, path)
+            }
+            ChartData::Series { labels, series } => ChartTable::new(labels.clone(), series.clone()),
+        }
+    }
+}
+
+impl ChartTable {
+    fn new(labels: Vec<String>, series: Vec<ChartSeries>) -> Result<Self> {
+        if labels.is_empty() || series.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Chart needs at least one row and one series"
+            ));
+        }
+        for column in &series {
+            if column.values.len() != labels.len() {
+                return Err(anyhow::anyhow!(
+                    "Chart series {:?} has {} values for {} labels",
+                    column.name,
+                    column.values.len(),
+                    labels.len()
+                ));
+            }
+            if column.values.iter().any(|value| !value.is_finite()) {
+                return Err(anyhow::anyhow!(
+                    "Chart series {:?} has a value that is not a finite number",
+                    column.name
+                ));
+            }
+        }
+        Ok(Self { labels, series })
+    }
+
+    /// Decimal places the data is written with, at most 2
+    fn decimals(&self) -> usize {
+        let values = self.series.iter().flat_map(|column| &column.values);
+        values
+            .map(|value| {
+                (0..2)
+                    .find(|&places| {
+                        let scaled = value * 10f64.powi(places as i32);
+                        (scaled - scaled.round()).abs() < 1e-6
+                    })
+                    .unwrap_or(2)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Parse CSV with a header row; `source` names the data in errors
+fn parse_csv(reader: impl Read, source: &str) -> Result<ChartTable> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader
+        .headers()
+        .map_err(|e| anyhow::anyhow!("Failed to read chart data {}: {}", source, e))?
+        .clone();
+    if headers.len() < 2 {
+        return Err(anyhow::anyhow!(
+            "Chart data {} needs a label column followed by at least one value column",
+            source
+        ));
+    }
+
+    let mut labels = Vec::new();
+    let mut series: Vec<ChartSeries> = headers
+        .iter()
+        .skip(1)
+        .map(|name| ChartSeries {
+            name: name.to_string(),
+            values: Vec::new(),
+        })
+        .collect();
+    for (row, record) in reader.records().enumerate() {
+        let record =
+            record.map_err(|e| anyhow::anyhow!("Failed to read chart data {}: {}", source, e))?;
+        labels.push(record[0].to_string());
+        for (column, field) in series.iter_mut().zip(record.iter().skip(1)) {
+            let value = field.parse::<f64>().map_err(|_| {
+                anyhow::anyhow!(
+                    "Chart data {} line {}: {} value {:?} is not a number",
+                    source,
+                    row + 2,
+                    column.name,
+                    field
+                )
+            })?;
+            column.values.push(value);
+        }
+    }
+
+    ChartTable::new(labels, series)
+}
+
+/// Value axis from `low` to `high` with a tick every `step`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Axis {
+    low: f64,
+    high: f64,
+    step: f64,
+}
+
+impl Axis {
+    /// Round numbers around the data and zero, with about five steps
+    fn around(table: &ChartTable) -> Self {
+        let values = table.series.iter().flat_map(|column| &column.values);
+        let (min, max) = values.fold((0.0f64, 0.0f64), |(min, max), value| {
+            (min.min(*value), max.max(*value))
+        });
+        let span = if max > min { max - min } else { 1.0 };
+
+        let raw = span / 5.0;
+        let magnitude = 10f64.powf(raw.log10().floor());
+        let step = match raw / magnitude {
+            fraction if fraction <= 1.0 => 1.0,
+            fraction if fraction <= 2.0 => 2.0,
+            fraction if fraction <= 5.0 => 5.0,
+            _ => 10.0,
+        } * magnitude;
+
+        let low = (min / step).floor() * step;
+        let high = (max / step).ceil() * step;
+        Self {
+            low,
+            high: if high > low { high } else { low + step }, // all zero
+            step,
+        }
+    }
+
+    fn ticks(&self) -> impl Iterator<Item = f64> + '_ {
+        // Multiples of the step, so zero is exactly zero
+        let first = (self.low / self.step).round();
+        let count = ((self.high - self.low) / self.step).round() as usize;
+        (0..=count).map(move |index| (first + index as f64) * self.step)
+    }
+
+    /// Decimal places the tick labels need
+    fn decimals(&self) -> usize {
+        (-self.step.log10().floor()).max(0.0) as usize
+    }
+
+    /// Position of `value` between `bottom` and `top` in px
+    fn position(&self, value: f64, (top, bottom): (f32, f32)) -> f32 {
+        let fraction = ((value - self.low) / (self.high - self.low)) as f32;
+        bottom - fraction * (bottom - top)
+    }
+}
+
+/// Value as drawn on the chart: with thousands separated up to 10,000,
+/// then shortened to k and M
+fn format_value(value: f64, decimals: usize) -> String {
+    let magnitude = value.abs();
+    let short = |scaled: f64, suffix: &str| {
+        let text = format!("{:.1}", scaled);
+        format!("{}{}", text.trim_end_matches(".0"), suffix)
+    };
+    if magnitude >= 1e6 {
+        return short(value / 1e6, "M");
+    }
+    if magnitude >= 1e4 {
+        return short(value / 1e3, "k");
+    }
+
+    let text = format!("{:.*}", decimals, value);
+    let (whole, fraction) = text.split_at(text.find('.').unwrap_or(text.len()));
+    let (sign, digits) = whole.split_at(whole.starts_with('-') as usize);
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+/// A color per series: the accent, then hues spread evenly around it. A grey
+/// accent has no hue to turn, so the others get a default saturation.
+fn series_colors(accent: Color, count: usize) -> Vec<Color> {
+    let (hue, saturation, lightness) = accent.to_hsl();
+    let (saturation, lightness) = if saturation < 0.15 {
+        (0.65, 0.6)
+    } else {
+        (saturation, lightness)
+    };
+    (0..count)
+        .map(|index| match index {
+            0 => accent,
+            _ => Color::from_hsl(
+                hue + index as f32 * 360.0 / count as f32,
+                saturation,
+                lightness,
+            ),
+        })
+        .collect()
+}
+
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// A chart fitted to an area of the frame, drawn at any point of its animation
+pub(crate) struct Chart {
+    kind: ChartKind,
+    table: ChartTable,
+    fonts: FontChain,
+    colors: Vec<Color>,
+    text_color: Color,
+    area: (f32, f32, f32, f32),
+    label_size: f32, // axis labels, names and values
+    decimals: usize,
+    axis: Axis,
+    plot: (f32, f32, f32, f32), // inside the axes and labels, bars and lines only
+    legend: Vec<(f32, f32)>,    // swatch position per series
+    value_size: f32,            // 0 when value labels don't fit above the bars
+}
+
+impl Chart {
+    /// Fit the chart to `area` (left, top, right, bottom), labelled at `label_size`
+    pub(crate) fn new(
+        kind: ChartKind,
+        table: ChartTable,
+        fonts: FontChain,
+        area: (f32, f32, f32, f32),
+        label_size: f32,
+        text_color: Color,
+        accent_color: Color,
+    ) -> Result<Self> {
+        let (left, top, right, bottom) = area;
+        let line = label_size * 1.4;
+        if right - left < label_size * 8.0 || bottom - top < line * 4.0 {
+            return Err(anyhow::anyhow!(
+                "Chart needs at least {:.0}x{:.0}px below the title, {:.0}x{:.0}px are left",
+                label_size * 8.0,
+                line * 4.0,
+                right - left,
+                bottom - top
+            ));
+        }
+
+        let colors = series_colors(accent_color, table.series.len());
+        let decimals = table.decimals();
+        let axis = Axis::around(&table);
+        let mut chart = Self {
+            kind,
+            table,
+            fonts,
+            colors,
+            text_color,
+            area,
+            label_size,
+            decimals,
+            axis,
+            plot: area,
+            legend: Vec::new(),
+            value_size: 0.0,
+        };
+        if kind == ChartKind::BarRace {
+            return Ok(chart);
+        }
+
+        // Legend rows on top, wrapped to the width, when there is more than one series
+        let mut legend_bottom = top;
+        if chart.table.series.len() > 1 {
+            let (mut x, mut y) = (left, top);
+            let mut legend = Vec::new();
+            for column in &chart.table.series {
+                let width = label_size * 1.2 + chart.measure(&column.name) + label_size;
+                if x > left && x + width > right {
+                    (x, y) = (left, y + line);
+                }
+                legend.push((x, y));
+                x += width;
+            }
+            chart.legend = legend;
+            legend_bottom = y + line * 1.5;
+        }
+
+        let tick_width = axis
+            .ticks()
+            .map(|tick| chart.measure(&format_value(tick, axis.decimals())))
+            .fold(0.0, f32::max);
+        let below_zero = if axis.low < 0.0 { line } else { 0.0 };
+        chart.plot = (
+            left + tick_width + label_size * 0.5,
+            legend_bottom + line,
+            right,
+            bottom - line - below_zero,
+        );
+
+        // Value labels as wide as a bar at most, left out when that is too small to read
+        if kind == ChartKind::Bar {
+            let widest = chart
+                .table
+                .series
+                .iter()
+                .flat_map(|column| &column.values)
+                .map(|value| chart.measure(&format_value(*value, decimals)))
+                .fold(0.0, f32::max);
+            let fit = label_size * chart.bar_width() / widest.max(1.0);
+            chart.value_size = if fit >= label_size * 0.5 {
+                fit.min(label_size)
+            } else {
+                0.0
+            };
+        }
+
+        Ok(chart)
+    }
+
+    fn measure(&self, text: &str) -> f32 {
+        text::measure_text(&self.fonts, self.label_size, text)
+    }
+
+    /// Width of the plot given to each row
+    fn slot_width(&self) -> f32 {
+        (self.plot.2 - self.plot.0) / self.table.labels.len() as f32
+    }
+
+    fn bar_width(&self) -> f32 {
+        self.slot_width() * 0.7 / self.table.series.len() as f32
+    }
+
+    /// How far the chart has grown at `time`, 0.0 - 1.0
+    pub(crate) fn progress(time: f64, duration: f64) -> f32 {
+        (time / (duration * GROW)).clamp(0.0, 1.0) as f32
+    }
+
+    /// The whole area the chart draws in, for safe-area checks
+    pub(crate) fn text_box(&self) -> TextBox {
+        let (left, top, right, bottom) = self.area;
+        TextBox {
+            label: "chart".to_string(),
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Draw the chart `progress` (0.0 - 1.0) of the way through its animation
+    pub(crate) fn draw(&self, image: &mut RgbImage, progress: f32) {
+        match self.kind {
+            ChartKind::Bar => {
+                self.draw_axes(image);
+                self.draw_bars(image, progress);
+            }
+            ChartKind::Line => {
+                self.draw_axes(image);
+                self.draw_lines(image, progress);
+            }
+            ChartKind::BarRace => self.draw_race(image, progress),
+        }
+    }
+
+    /// Legend, grid lines with their values and the row labels under the plot
+    fn draw_axes(&self, image: &mut RgbImage) {
+        let size = self.label_size;
+        for ((x, y), (column, color)) in self
+            .legend
+            .iter()
+            .zip(self.table.series.iter().zip(&self.colors))
+        {
+            let swatch = size * 0.8;
+            text::fill_rounded_rect(
+                image,
+                (*x, y + (size - swatch) / 2.0),
+                (x + swatch, y + (size + swatch) / 2.0),
+                swatch / 4.0,
+                *color,
+            );
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                &column.name,
+                size,
+                x + size * 1.2,
+                y + size * 0.85,
+                self.text_color,
+            );
+        }
+
+        let (left, top, right, bottom) = self.plot;
+        let mut grid = self.text_color;
+        for tick in self.axis.ticks() {
+            let y = self.axis.position(tick, (top, bottom));
+            let strength = if tick == 0.0 { 0.6 } else { 0.2 };
+            grid.a = (self.text_color.a as f32 * strength).round() as u8;
+            let thickness = if tick == 0.0 { 2.0 } else { 1.0 };
+            text::fill_rounded_rect(
+                image,
+                (left, y - thickness / 2.0),
+                (right, y + thickness / 2.0),
+                0.0,
+                grid,
+            );
+
+            let label = format_value(tick, self.axis.decimals());
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                &label,
+                size,
+                left - size * 0.5 - self.measure(&label),
+                y + size * 0.35,
+                self.text_color,
+            );
+        }
+
+        // Every nth label when they don't all fit side by side
+        let widest = self
+            .table
+            .labels
+            .iter()
+            .map(|label| self.measure(label))
+            .fold(0.0, f32::max);
+        let every = ((widest + size * 0.5) / self.slot_width()).ceil().max(1.0) as usize;
+        let below = bottom + if self.axis.low < 0.0 { size * 1.4 } else { 0.0 };
+        for (index, label) in self.table.labels.iter().enumerate().step_by(every) {
+            let center = left + self.slot_width() * (index as f32 + 0.5);
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                label,
+                size,
+                center - self.measure(label) / 2.0,
+                below + size * 1.15,
+                self.text_color,
+            );
+        }
+    }
+
+    /// Bars of each row grow in turn, overlapping so the last finishes on time
+    fn draw_bars(&self, image: &mut RgbImage, progress: f32) {
+        let (left, top, _, bottom) = self.plot;
+        let rows = self.table.labels.len();
+        let window = if rows > 1 { 0.5 } else { 1.0 };
+        let bar_width = self.bar_width();
+        let zero = self.axis.position(0.0, (top, bottom));
+
+        for row in 0..rows {
+            let start = row as f32 / rows as f32 * (1.0 - window);
+            let grown = ease_out(((progress - start) / window).clamp(0.0, 1.0));
+            if grown <= 0.0 {
+                continue;
+            }
+
+            let group_left = left + self.slot_width() * (row as f32 + 0.5)
+                - bar_width * self.colors.len() as f32 / 2.0;
+            for (index, (column, color)) in self.table.series.iter().zip(&self.colors).enumerate() {
+                let value = column.values[row] * grown as f64;
+                let y = self.axis.position(value, (top, bottom));
+                let x = group_left + bar_width * index as f32;
+                let gap = (bar_width * 0.08).min(4.0);
+                text::fill_rounded_rect(
+                    image,
+                    (x + gap, y.min(zero)),
+                    (x + bar_width - gap, y.max(zero)),
+                    (bar_width / 8.0).min(6.0),
+                    *color,
+                );
+
+                if self.value_size > 0.0 {
+                    let label = format_value(value, self.decimals);
+                    let width = text::measure_text(&self.fonts, self.value_size, &label);
+                    let baseline = if value >= 0.0 {
+                        y - self.value_size * 0.4
+                    } else {
+                        y + self.value_size * 1.15
+                    };
+                    text::draw_text_run(
+                        image,
+                        &self.fonts,
+                        &label,
+                        self.value_size,
+                        x + (bar_width - width) / 2.0,
+                        baseline,
+                        self.text_color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Lines reach each row in turn, with the current value at their tip
+    fn draw_lines(&self, image: &mut RgbImage, progress: f32) {
+        let (left, top, _, bottom) = self.plot;
+        let rows = self.table.labels.len();
+        let head = progress * (rows - 1) as f32;
+        let thickness = (self.label_size * 0.15).max(3.0);
+        let point = |row: usize, value: f64| {
+            (
+                left + self.slot_width() * (row as f32 + 0.5),
+                self.axis.position(value, (top, bottom)),
+            )
+        };
+
+        for (column, color) in self.table.series.iter().zip(&self.colors) {
+            let last = (head.floor() as usize).min(rows - 1);
+            let fraction = head - last as f32;
+            for row in 0..last {
+                let from = point(row, column.values[row]);
+                let to = point(row + 1, column.values[row + 1]);
+                text::draw_line(image, from, to, thickness, *color);
+            }
+
+            // Part of the way to the next row
+            let value = match column.values.get(last + 1) {
+                Some(next) => column.values[last] + (next - column.values[last]) * fraction as f64,
+                None => column.values[last],
+            };
+            let (x, y) = point(last, value);
+            let tip = (x + self.slot_width() * fraction, y);
+            text::draw_line(
+                image,
+                point(last, column.values[last]),
+                tip,
+                thickness,
+                *color,
+            );
+            for row in 0..=last {
+                fill_circle(image, point(row, column.values[row]), thickness, *color);
+            }
+
+            fill_circle(image, tip, thickness * 1.5, *color);
+            let label = format_value(value, self.decimals);
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                &label,
+                self.label_size,
+                tip.0 - self.measure(&label) / 2.0,
+                tip.1 - thickness * 1.5 - self.label_size * 0.4,
+                self.text_color,
+            );
+        }
+    }
+
+    /// Value of every series `position` rows in, between two rows while it moves
+    fn race_values(&self, position: f32) -> Vec<f64> {
+        let row = (position.floor() as usize).min(self.table.labels.len() - 1);
+        let next = (row + 1).min(self.table.labels.len() - 1);
+        let fraction = (position - row as f32) as f64;
+        self.table
+            .series
+            .iter()
+            .map(|column| {
+                let (from, to) = (column.values[row], column.values[next]);
+                (from + (to - from) * fraction).max(0.0)
+            })
+            .collect()
+    }
+
+    /// Series as horizontal bars sorted by their value between two rows,
+    /// sliding past each other as their values cross, with the row label below
+    fn draw_race(&self, image: &mut RgbImage, progress: f32) {
+        let (left, top, right, bottom) = self.area;
+        let position = progress * (self.table.labels.len() - 1) as f32;
+        let values = self.race_values(position);
+        let max = values.iter().copied().fold(0.0, f64::max);
+        let max = if max > 0.0 { max } else { 1.0 };
+
+        // Ranks averaged over the last 0.3 rows, so overtaking bars slide into place
+        let samples = 8;
+        let mut ranks = vec![0.0; values.len()];
+        for sample in 0..samples {
+            let earlier = (position - 0.3 * sample as f32 / (samples - 1) as f32).max(0.0);
+            let mut order: Vec<usize> = (0..values.len()).collect();
+            let values = self.race_values(earlier);
+            order.sort_by(|a, b| values[*b].total_cmp(&values[*a]));
+            for (rank, index) in order.into_iter().enumerate() {
+                ranks[index] += rank as f32 / samples as f32;
+            }
+        }
+
+        // Names in a column on the left, shrunk to at most a third of the width
+        let widest_name = self
+            .table
+            .series
+            .iter()
+            .map(|column| self.measure(&column.name))
+            .fold(0.0, f32::max);
+        let name_size = self.label_size * ((right - left) / 3.0 / widest_name.max(1.0)).min(1.0);
+        let widest_value = self
+            .table
+            .series
+            .iter()
+            .flat_map(|column| &column.values)
+            .map(|value| self.measure(&format_value(*value, self.decimals)))
+            .fold(0.0, f32::max);
+        let bars_left = left + widest_name.min((right - left) / 3.0) + self.label_size * 0.5;
+        let bars_right = right - widest_value - self.label_size * 0.5;
+
+        let period_size = self.label_size * 2.0;
+        let bars_bottom = bottom - period_size * 1.4;
+        let visible = self.table.series.len().min(RACE_BARS);
+        // A few series get bars of a sensible height rather than the whole area
+        let slot = ((bars_bottom - top) / visible as f32).min(self.label_size * 2.5);
+        let height = slot * 0.75;
+
+        for (index, column) in self.table.series.iter().enumerate() {
+            let rank = ranks[index];
+            // Bars entering or leaving the top places fade at the bottom edge
+            let fade = (visible as f32 - rank).clamp(0.0, 1.0);
+            if fade <= 0.0 {
+                continue;
+            }
+            let faded = |mut color: Color| {
+                color.a = (color.a as f32 * fade).round() as u8;
+                color
+            };
+
+            let y = top + rank * slot + (slot - height) / 2.0;
+            let length = (values[index] / max) as f32 * (bars_right - bars_left);
+            text::fill_rounded_rect(
+                image,
+                (bars_left, y),
+                (bars_left + length.max(1.0), y + height),
+                (height / 8.0).min(6.0),
+                faded(self.colors[index]),
+            );
+
+            let baseline = y + height / 2.0 + self.label_size * 0.35;
+            let name_width = text::measure_text(&self.fonts, name_size, &column.name);
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                &column.name,
+                name_size,
+                bars_left - self.label_size * 0.5 - name_width,
+                baseline,
+                faded(self.text_color),
+            );
+            text::draw_text_run(
+                image,
+                &self.fonts,
+                &format_value(values[index], self.decimals),
+                self.label_size,
+                bars_left + length + self.label_size * 0.5,
+                baseline,
+                faded(self.text_color),
+            );
+        }
+
+        let label = &self.table.labels[position.round() as usize];
+        let width = text::measure_text(&self.fonts, period_size, label);
+        let mut color = self.text_color;
+        color.a = (color.a as f32 * 0.6).round() as u8;
+        text::draw_text_run(
+            image,
+            &self.fonts,
+            label,
+            period_size,
+            right - width,
+            top + slot * visible as f32 + period_size,
+            color,
+        );
+    }
+}
+
+fn fill_circle(image: &mut RgbImage, (x, y): (f32, f32), radius: f32, color: Color) {
+    text::fill_rounded_rect(
+        image,
+        (x - radius, y - radius),
+        (x + radius, y + radius),
+        radius,
+        color,
+    );
+}
+
+/// Chart growing over the first part of the video, over a still background
+pub(crate) struct ChartRenderer {
+    background: RgbImage,
+    chart: Chart,
+    duration: f64,
+    drawn: Option<f32>, // progress of the last frame
+}
+
+impl ChartRenderer {
+    pub(crate) fn new(background: RgbImage, chart: Chart, duration: f64) -> Self {
+        Self {
+            background,
+            chart,
+            duration,
+            drawn: None,
+        }
+    }
+}
+
+impl FrameRenderer for ChartRenderer {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let progress = Chart::progress(time, self.duration);
+        if self.drawn == Some(progress) {
+            return Ok(None);
+        }
+        self.drawn = Some(progress);
+
+        let mut frame = self.background.clone();
+        self.chart.draw(&mut frame, progress);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_and_inline_series() {
+        let csv = "week, views, likes\nW1, 1200, 80\nW2, 3400.5, 95\n";
+        let table = parse_csv(csv.as_bytes(), "stats.csv").unwrap();
+        assert_eq!(table.labels, vec!["W1", "W2"]);
+        assert_eq!(table.series[0].name, "views");
+        assert_eq!(table.series[0].values, vec![1200.0, 3400.5]);
+        assert_eq!(table.series[1].values, vec![80.0, 95.0]);
+        assert_eq!(table.decimals(), 1);
+
+        let error = parse_csv("week,views\nW1,lots\n".as_bytes(), "stats.csv").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+        assert!(parse_csv("week\nW1\n".as_bytes(), "stats.csv").is_err());
+
+        let data: ChartData = serde_json::from_str(
+            r#"{"Series": {"labels": ["a", "b"], "series": [{"name": "n", "values": [1]}]}}"#,
+        )
+        .unwrap();
+        assert!(data.load().is_err()); // one value for two labels
+    }
+
+    #[test]
+    fn test_axis_and_value_formatting() {
+        let table = ChartTable::new(
+            vec!["a".into(), "b".into()],
+            vec![ChartSeries {
+                name: "n".into(),
+                values: vec![-30.0, 870.0],
+            }],
+        )
+        .unwrap();
+        let axis = Axis::around(&table);
+        assert_eq!((axis.low, axis.high, axis.step), (-200.0, 1000.0, 200.0));
+        assert_eq!(axis.position(-200.0, (0.0, 600.0)), 600.0);
+        assert_eq!(axis.position(1000.0, (0.0, 600.0)), 0.0);
+
+        assert_eq!(format_value(1234.0, 0), "1,234");
+        assert_eq!(format_value(-999.04, 1), "-999.0");
+        assert_eq!(format_value(45_600.0, 0), "45.6k");
+        assert_eq!(format_value(2_000_000.0, 0), "2M");
+    }
+}
@@ -0,0 +1,734 @@
+use ab_glyph::{Font, PxScale, ScaleFont};
+use anyhow::Result;
+use image::{imageops, RgbImage};
+
+use super::animation::FrameRenderer;
+use super::color::Color;
+use super::fonts::FontChain;
+use super::layout::TextBox;
+use super::text;
+
+/// What a piece of source code is, for coloring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Plain,
+    Keyword,
+    Type,
+    Function,
+    String,
+    Number,
+    Comment,
+}
+
+impl TokenKind {
+    /// One Dark editor colors
+    fn color(self) -> Color {
+        match self {
+            TokenKind::Plain => Color::rgb(171, 178, 191),
+            TokenKind::Keyword => Color::rgb(198, 120, 221),
+            TokenKind::Type => Color::rgb(229, 192, 123),
+            TokenKind::Function => Color::rgb(97, 175, 239),
+            TokenKind::String => Color::rgb(152, 195, 121),
+            TokenKind::Number => Color::rgb(209, 154, 102),
+            TokenKind::Comment => Color::rgb(127, 132, 142),
+        }
+    }
+}
+
+/// One line of code as colored runs of text
+pub(crate) type CodeLine = Vec<(TokenKind, String)>;
+
+/// Lexical rules of a language, enough to color keywords, strings and comments
+struct Syntax {
+    keywords: &'static [&'static str],
+    case_sensitive: bool,
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    char_literals: bool, // `'` quotes a single character, as in Rust or C
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "False", "finally", "for", "from", "global", "if", "import", "in", "is",
+    "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "self", "True", "try",
+    "while", "with", "yield",
+];
+
+const JAVASCRIPT_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "of",
+    "return",
+    "static",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "false",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "nil",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "true",
+    "type",
+    "var",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "abstract",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "extern",
+    "false",
+    "final",
+    "float",
+    "for",
+    "if",
+    "implements",
+    "import",
+    "int",
+    "interface",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "nullptr",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "typedef",
+    "union",
+    "unsigned",
+    "using",
+    "var",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+    "function", "if", "in", "local", "return", "then", "until", "while",
+];
+
+const SQL_KEYWORDS: &[&str] = &[
+    "AND", "AS", "ASC", "BY", "CREATE", "DELETE", "DESC", "DISTINCT", "DROP", "FROM", "GROUP",
+    "HAVING", "IN", "INSERT", "INTO", "IS", "JOIN", "LEFT", "LIMIT", "NOT", "NULL", "ON", "OR",
+    "ORDER", "SELECT", "SET", "TABLE", "UPDATE", "VALUES", "WHERE", "WITH",
+];
+
+/// Rules for a `language` name or file extension; unknown languages still get
+/// strings, numbers and the common comment styles
+fn syntax(language: &str) -> Syntax {
+    let c_like = |keywords| Syntax {
+        keywords,
+        case_sensitive: true,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        char_literals: true,
+    };
+
+    match language.trim().to_ascii_lowercase().as_str() {
+        "rust" | "rs" => c_like(RUST_KEYWORDS),
+        "go" | "golang" => c_like(GO_KEYWORDS),
+        "c" | "h" | "cpp" | "c++" | "java" | "kotlin" | "kt" | "c#" | "csharp" | "cs" => {
+            c_like(C_KEYWORDS)
+        }
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Syntax {
+            char_literals: false,
+            ..c_like(JAVASCRIPT_KEYWORDS)
+        },
+        "python" | "py" => Syntax {
+            keywords: PYTHON_KEYWORDS,
+            case_sensitive: true,
+            line_comments: &["#"],
+            block_comment: None,
+            char_literals: false,
+        },
+        "bash" | "sh" | "shell" | "zsh" => Syntax {
+            keywords: SHELL_KEYWORDS,
+            case_sensitive: true,
+            line_comments: &["#"],
+            block_comment: None,
+            char_literals: false,
+        },
+        "sql" => Syntax {
+            keywords: SQL_KEYWORDS,
+            case_sensitive: false,
+            line_comments: &["--"],
+            block_comment: Some(("/*", "*/")),
+            char_literals: false,
+        },
+        _ => Syntax {
+            keywords: &[],
+            case_sensitive: true,
+            line_comments: &["//", "#"],
+            block_comment: Some(("/*", "*/")),
+            char_literals: false,
+        },
+    }
+}
+
+/// Split `code` into lines of colored tokens. Tabs become four spaces.
+pub(crate) fn highlight(language: &str, code: &str) -> Vec<CodeLine> {
+    let syntax = syntax(language);
+    let chars = code
+        .replace("\r\n", "\n")
+        .replace('\t', "    ")
+        .chars()
+        .collect::<Vec<_>>();
+    let starts_with = |at: usize, pattern: &str| {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(offset, c)| chars.get(at + offset) == Some(&c))
+    };
+
+    let mut lines: Vec<CodeLine> = vec![Vec::new()];
+    let mut at = 0;
+    while at < chars.len() {
+        let c = chars[at];
+        let (kind, length) = if let Some(comment) = syntax
+            .line_comments
+            .iter()
+            .find(|comment| starts_with(at, comment))
+        {
+            let end = chars[at..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |offset| at + offset);
+            (TokenKind::Comment, (end - at).max(comment.len()))
+        } else if let Some((open, close)) = syntax
+            .block_comment
+            .filter(|(open, _)| starts_with(at, open))
+        {
+            let end = (at + open.len()..chars.len())
+                .find(|&end| starts_with(end, close))
+                .map_or(chars.len(), |end| end + close.len());
+            (TokenKind::Comment, end - at)
+        } else if c == '\'' && syntax.char_literals {
+            // 'x' and '\n' are characters, anything else (a Rust lifetime) is plain
+            let literal = if chars.get(at + 1) == Some(&'\\') {
+                chars
+                    .get(at + 3..)
+                    .and_then(|rest| rest.iter().take(8).position(|&c| c == '\''))
+                    .map(|offset| offset + 4)
+            } else {
+                (chars.get(at + 2) == Some(&'\'')).then_some(3)
+            };
+            match literal {
+                Some(length) => (TokenKind::String, length),
+                None => (TokenKind::Plain, 1),
+            }
+        } else if matches!(c, '"' | '\'' | '`') {
+            // Only backtick strings run past the end of a line
+            let mut end = at + 1;
+            while end < chars.len() && chars[end] != c && (c == '`' || chars[end] != '\n') {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            (TokenKind::String, (end + 1).min(chars.len()) - at)
+        } else if c.is_ascii_digit() {
+            let length = chars[at..]
+                .iter()
+                .zip(chars[at..].iter().skip(1).chain(Some(&' ')))
+                .position(|(&c, &next)| {
+                    !(c.is_ascii_alphanumeric() || c == '_' || (c == '.' && next.is_ascii_digit()))
+                })
+                .unwrap_or(chars.len() - at);
+            (TokenKind::Number, length)
+        } else if c.is_alphabetic() || c == '_' {
+            let length = chars[at..]
+                .iter()
+                .position(|&c| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(chars.len() - at);
+            let word = chars[at..at + length].iter().collect::<String>();
+            let call = chars[at + length..].iter().find(|c| **c != ' ') == Some(&'(');
+            let macro_call =
+                chars.get(at + length) == Some(&'!') && chars.get(at + length + 1) != Some(&'=');
+            let kind = if is_keyword(&syntax, &word) {
+                TokenKind::Keyword
+            } else if call || macro_call {
+                TokenKind::Function
+            } else if c.is_uppercase() {
+                TokenKind::Type
+            } else {
+                TokenKind::Plain
+            };
+            (kind, length)
+        } else {
+            (TokenKind::Plain, 1)
+        };
+
+        let token = chars[at..at + length].iter().collect::<String>();
+        push_token(&mut lines, kind, &token);
+        at += length;
+    }
+
+    lines
+}
+
+fn is_keyword(syntax: &Syntax, word: &str) -> bool {
+    if syntax.case_sensitive {
+        syntax.keywords.contains(&word)
+    } else {
+        syntax
+            .keywords
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(word))
+    }
+}
+
+/// Append a token, starting new lines at its line breaks and merging it into
+/// the previous run when both have the same kind
+fn push_token(lines: &mut Vec<CodeLine>, kind: TokenKind, token: &str) {
+    for (index, part) in token.split('\n').enumerate() {
+        if index > 0 {
+            lines.push(Vec::new());
+        }
+        if part.is_empty() {
+            continue;
+        }
+        let line = lines.last_mut().expect("there is always a line");
+        match line.last_mut() {
+            Some((last, text)) if *last == kind => text.push_str(part),
+            _ => line.push((kind, part.to_string())),
+        }
+    }
+}
+
+// Window proportions, in multiples of the code font size
+const TITLE_BAR: f32 = 2.0;
+const PADDING: f32 = 0.8;
+const LINE_SPACING: f32 = 1.4;
+const GUTTER_GAP: usize = 2; // characters between line numbers and code
+
+const WINDOW_COLOR: Color = Color::rgb(40, 44, 52);
+const TITLE_BAR_COLOR: Color = Color::rgb(33, 37, 43);
+const LINE_NUMBER_COLOR: Color = Color::rgb(92, 99, 112);
+const BUTTON_COLORS: [Color; 3] = [
+    Color::rgb(255, 95, 86),
+    Color::rgb(255, 189, 46),
+    Color::rgb(39, 201, 63),
+];
+
+/// A code snippet in an editor window, sized to fit an area of the frame
+pub(crate) struct CodeWindow {
+    lines: Vec<CodeLine>,
+    language: String,
+    fonts: FontChain,
+    font_size: f32,
+    advance: f32,                 // width of one character at `font_size`
+    bounds: (f32, f32, f32, f32), // left, top, right, bottom of the window
+    visible_lines: usize,         // rows that fit; fewer than `lines` means the code scrolls
+}
+
+impl CodeWindow {
+    /// Scale the code to the largest font size, up to `max_font_size`, at which
+    /// every line fits the width of `area` and every row its height. Below
+    /// `min_font_size` the code is kept readable and scrolls instead.
+    /// The window spans the width of `area` and starts at its top.
+    pub(crate) fn new(
+        language: &str,
+        code: &str,
+        area: (f32, f32, f32, f32),
+        max_font_size: f32,
+        min_font_size: f32,
+    ) -> Result<Self> {
+        let mut lines = highlight(language, code);
+        while lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        if lines.iter().all(|line| line.is_empty()) {
+            return Err(anyhow::anyhow!("CodeSnippet has no code to show"));
+        }
+
+        let fonts = FontChain::monospace();
+        let advance_per_px = text::measure_text(&fonts, 100.0, "0") / 100.0;
+        let columns = lines
+            .iter()
+            .map(|line| line.iter().map(|(_, text)| text.chars().count()).sum())
+            .max()
+            .unwrap_or(0)
+            + number_width(lines.len())
+            + GUTTER_GAP;
+
+        let (left, top, right, bottom) = area;
+        let (width, height) = (right - left, bottom - top);
+        let chrome = TITLE_BAR + PADDING * 2.0;
+        let fit_width = width / (advance_per_px * columns as f32 + PADDING * 2.0);
+        let fit_height = height / (chrome + LINE_SPACING * lines.len() as f32);
+        let font_size = max_font_size
+            .min(fit_width)
+            .min(fit_height.max(min_font_size.min(max_font_size)));
+
+        let visible_lines =
+            (((height / font_size - chrome) / LINE_SPACING).floor() as usize).clamp(1, lines.len());
+        let window_height = font_size * (chrome + LINE_SPACING * visible_lines as f32);
+
+        Ok(Self {
+            lines,
+            language: language.trim().to_string(),
+            fonts,
+            font_size,
+            advance: advance_per_px * font_size,
+            bounds: (left, top, right, top + window_height),
+            visible_lines,
+        })
+    }
+
+    /// Move the window down by `dy` px
+    pub(crate) fn offset(&mut self, dy: f32) {
+        self.bounds.1 += dy;
+        self.bounds.3 += dy;
+    }
+
+    /// Where the window is drawn, for safe-area checks
+    pub(crate) fn text_box(&self) -> TextBox {
+        let (left, top, right, bottom) = self.bounds;
+        TextBox {
+            label: "code".to_string(),
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Lines shown at `time` and how many lines the code is scrolled by.
+    /// Revealed lines appear evenly over the first 80% of the video and the
+    /// view follows the newest one; otherwise long code holds on its start,
+    /// scrolls to the end and holds again.
+    pub(crate) fn frame_at(&self, time: f64, duration: f64, reveal: bool) -> (usize, f32) {
+        let count = self.lines.len();
+        let hidden = count - self.visible_lines;
+        if reveal {
+            let progress = time / (duration * 0.8).max(f64::EPSILON);
+            let shown = ((progress * count as f64).floor() as usize + 1).min(count);
+            return (shown, shown.saturating_sub(self.visible_lines) as f32);
+        }
+
+        let progress = ((time / duration.max(f64::EPSILON) - 0.15) / 0.7).clamp(0.0, 1.0) as f32;
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        (count, eased * hidden as f32)
+    }
+
+    /// Draw the window with the first `shown` lines, scrolled by `scroll` lines
+    pub(crate) fn draw(&self, image: &mut RgbImage, shown: usize, scroll: f32) {
+        let (left, top, right, bottom) = self.bounds;
+        let size = self.font_size;
+        let radius = size * 0.6;
+        let bar_bottom = top + size * TITLE_BAR;
+
+        // Title bar, with square corners where it meets the code
+        text::fill_rounded_rect(image, (left, top), (right, bottom), radius, WINDOW_COLOR);
+        text::fill_rounded_rect(
+            image,
+            (left, top),
+            (right, bar_bottom + radius),
+            radius,
+            TITLE_BAR_COLOR,
+        );
+        text::fill_rounded_rect(
+            image,
+            (left, bar_bottom),
+            (right, bar_bottom + radius),
+            0.0,
+            WINDOW_COLOR,
+        );
+
+        let button = size * 0.6;
+        let button_top = top + (size * TITLE_BAR - button) / 2.0;
+        for (index, color) in BUTTON_COLORS.into_iter().enumerate() {
+            let x = left + size * PADDING + index as f32 * button * 1.6;
+            text::fill_rounded_rect(
+                image,
+                (x, button_top),
+                (x + button, button_top + button),
+                button / 2.0,
+                color,
+            );
+        }
+
+        let label_size = size * 0.8;
+        let label_width = text::measure_text(&self.fonts, label_size, &self.language);
+        let ascent = self.fonts.primary().as_scaled(PxScale::from(size)).ascent();
+        text::draw_text_run(
+            image,
+            &self.fonts,
+            &self.language,
+            label_size,
+            (left + right - label_width) / 2.0,
+            top + (size * TITLE_BAR + label_size * 0.7) / 2.0,
+            LINE_NUMBER_COLOR,
+        );
+
+        // Code is drawn into the body alone so scrolled lines are cut off at its edges
+        let clip_top = (bar_bottom + size * PADDING / 2.0).round().max(0.0) as u32;
+        let clip_bottom = ((bottom - size * PADDING / 2.0).round() as u32).min(image.height());
+        let clip_left = left.round().max(0.0) as u32;
+        let clip_right = (right.round() as u32).min(image.width());
+        if clip_bottom <= clip_top || clip_right <= clip_left {
+            return;
+        }
+        let mut body = imageops::crop_imm(
+            image,
+            clip_left,
+            clip_top,
+            clip_right - clip_left,
+            clip_bottom - clip_top,
+        )
+        .to_image();
+
+        let digits = number_width(self.lines.len());
+        let line_height = size * LINE_SPACING;
+        let first_baseline = bar_bottom + size * PADDING + ascent - clip_top as f32;
+        let code_x = left + size * PADDING - clip_left as f32;
+        let first = scroll.floor() as usize;
+        for (index, line) in self.lines.iter().enumerate().take(shown).skip(first) {
+            let baseline = first_baseline + (index as f32 - scroll) * line_height;
+            if baseline - ascent > body.height() as f32 {
+                break;
+            }
+
+            let number = (index + 1).to_string();
+            let number_x = code_x + (digits - number.len()) as f32 * self.advance;
+            text::draw_text_run(
+                &mut body,
+                &self.fonts,
+                &number,
+                size,
+                number_x,
+                baseline,
+                LINE_NUMBER_COLOR,
+            );
+
+            let mut x = code_x + (digits + GUTTER_GAP) as f32 * self.advance;
+            for (kind, token) in line {
+                x = text::draw_text_run(
+                    &mut body,
+                    &self.fonts,
+                    token,
+                    size,
+                    x,
+                    baseline,
+                    kind.color(),
+                );
+            }
+        }
+
+        imageops::replace(image, &body, clip_left as i64, clip_top as i64);
+    }
+}
+
+/// Digits in the largest line number
+fn number_width(lines: usize) -> usize {
+    lines.max(1).to_string().len()
+}
+
+/// A code window over a still background, redrawn when a line appears or the code scrolls
+pub(crate) struct CodeRenderer {
+    background: RgbImage,
+    window: CodeWindow,
+    reveal: bool,
+    duration: f64,
+    shown: Option<(usize, i64)>, // lines and scroll offset in px of the last frame
+}
+
+impl CodeRenderer {
+    pub(crate) fn new(
+        background: RgbImage,
+        window: CodeWindow,
+        reveal: bool,
+        duration: f64,
+    ) -> Self {
+        Self {
+            background,
+            window,
+            reveal,
+            duration,
+            shown: None,
+        }
+    }
+}
+
+impl FrameRenderer for CodeRenderer {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let (lines, scroll) = self.window.frame_at(time, self.duration, self.reveal);
+        let state = (
+            lines,
+            (scroll * self.window.font_size * LINE_SPACING).round() as i64,
+        );
+        if self.shown == Some(state) {
+            return Ok(None);
+        }
+        self.shown = Some(state);
+
+        let mut frame = self.background.clone();
+        self.window.draw(&mut frame, lines, scroll);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &CodeLine) -> Vec<(TokenKind, &str)> {
+        line.iter()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(kind, text)| (*kind, text.trim()))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let lines = highlight(
+            "rust",
+            "fn main() {\n    let s: &'static str = \"hi\"; // greet\n    println!(\"{}\", 42);\n}",
+        );
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            kinds(&lines[0])[..2],
+            [(TokenKind::Keyword, "fn"), (TokenKind::Function, "main")]
+        );
+
+        // A lifetime is not a character literal
+        let second = kinds(&lines[1]);
+        assert!(second.contains(&(TokenKind::Keyword, "static")));
+        assert!(second.contains(&(TokenKind::String, "\"hi\"")));
+        assert_eq!(second.last(), Some(&(TokenKind::Comment, "// greet")));
+
+        let third = kinds(&lines[2]);
+        assert_eq!(third[0], (TokenKind::Function, "println"));
+        assert!(third.contains(&(TokenKind::Number, "42")));
+
+        // Block comments keep their color across lines
+        let python = highlight("py", "def f():\n    return None  # done");
+        assert_eq!(kinds(&python[1])[0], (TokenKind::Keyword, "return"));
+        let block = highlight("js", "/* one\ntwo */ x");
+        assert_eq!(kinds(&block[1])[0], (TokenKind::Comment, "two */"));
+    }
+
+    #[test]
+    fn test_long_code_scales_then_scrolls() {
+        let area = (0.0, 0.0, 1000.0, 1200.0);
+        let short = CodeWindow::new("rust", "let x = 1;\nlet y = 2;", area, 40.0, 20.0).unwrap();
+        assert_eq!(short.font_size, 40.0);
+        assert_eq!(short.frame_at(5.0, 10.0, false), (2, 0.0));
+
+        // Too wide: shrinks until the longest line fits
+        let wide = "x".repeat(120);
+        let window = CodeWindow::new("rust", &wide, area, 40.0, 20.0).unwrap();
+        assert!(window.font_size < 40.0);
+        assert!(window.advance * 124.0 <= 1000.0);
+
+        // Too tall even at the minimum size: scrolls from the start to the end
+        let tall = (1..=80)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let window = CodeWindow::new("text", &tall, area, 40.0, 20.0).unwrap();
+        assert_eq!(window.font_size, 20.0);
+        assert!(window.visible_lines < 80);
+        let hidden = (80 - window.visible_lines) as f32;
+        assert_eq!(window.frame_at(1.0, 10.0, false), (80, 0.0));
+        assert_eq!(window.frame_at(9.0, 10.0, false), (80, hidden));
+
+        // Revealing lines follows the newest one
+        assert_eq!(window.frame_at(0.0, 10.0, true), (1, 0.0));
+        assert_eq!(window.frame_at(8.0, 10.0, true), (80, hidden));
+
+        assert!(CodeWindow::new("rust", "\n\n", area, 40.0, 20.0).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+use anyhow::Result;
+use image::Rgb;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// RGBA color parsed from the CSS forms content authors write:
+/// `#fff`, `#ffffff`, `#ffff`, `#ffffff80`, `rgb(255, 255, 255)`, `rgba(0, 0, 0, 0.6)`,
+/// `hsl(210, 50%, 40%)`, `hsla(...)` and CSS color names such as `white`.
+///
+/// Serializes to `#rrggbb`, or `#rrggbbaa` when not fully opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8, // 255 = opaque
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 255)
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// The color without its alpha, for surfaces that are always opaque
+    pub fn to_rgb(self) -> Rgb<u8> {
+        Rgb([self.r, self.g, self.b])
+    }
+
+    /// Alpha as 0.0 - 1.0
+    pub fn opacity(self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    /// Paint this color over `pixel` where `coverage` (0.0 - 1.0) of it is covered,
+    /// scaled by the color's own alpha
+    pub fn blend(self, pixel: &mut Rgb<u8>, coverage: f32) {
+        let alpha = (coverage * self.opacity()).clamp(0.0, 1.0);
+        for (channel, foreground) in [self.r, self.g, self.b].into_iter().enumerate() {
+            let background = pixel[channel] as f32;
+            pixel[channel] = (background + (foreground as f32 - background) * alpha).round() as u8;
+        }
+    }
+
+    /// Hue in degrees, saturation and lightness as 0.0 - 1.0
+    pub(crate) fn to_hsl(self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|channel| channel as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let chroma = max - min;
+        if chroma == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// Opaque color from `to_hsl` components
+    pub(crate) fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (r, g, b) = hsl_to_rgb(hue, saturation, lightness);
+        Self::rgb(r, g, b)
+    }
+
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).ok();
+        let byte = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+
+        match hex.len() {
+            // Short forms repeat each digit: #f80 = #ff8800
+            3 | 4 => {
+                let mut channels = [255u8; 4];
+                for (index, channel) in channels.iter_mut().take(hex.len()).enumerate() {
+                    *channel = digit(index)? * 17;
+                }
+                Some(Self::rgba(
+                    channels[0],
+                    channels[1],
+                    channels[2],
+                    channels[3],
+                ))
+            }
+            6 => Some(Self::rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Self::rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        }
+    }
+
+    /// Arguments of `rgb()` / `hsl()`, separated by commas or by spaces with
+    /// an optional `/ alpha`
+    fn parse_arguments(arguments: &str) -> Vec<&str> {
+        arguments
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|argument| !argument.is_empty())
+            .collect()
+    }
+
+    fn parse_function(name: &str, arguments: &str) -> Option<Self> {
+        let arguments = Self::parse_arguments(arguments);
+        let alpha = match arguments.len() {
+            3 => 255,
+            4 => parse_alpha(arguments[3])?,
+            _ => return None,
+        };
+
+        match name {
+            "rgb" | "rgba" => Some(Self::rgba(
+                parse_channel(arguments[0])?,
+                parse_channel(arguments[1])?,
+                parse_channel(arguments[2])?,
+                alpha,
+            )),
+            "hsl" | "hsla" => {
+                let hue = arguments[0].trim_end_matches("deg").parse::<f32>().ok()?;
+                let saturation = parse_percentage(arguments[1])?;
+                let lightness = parse_percentage(arguments[2])?;
+                let (r, g, b) = hsl_to_rgb(hue, saturation, lightness);
+                Some(Self::rgba(r, g, b, alpha))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let value = input.trim().to_ascii_lowercase();
+
+        let color = if let Some(hex) = value.strip_prefix('#') {
+            Self::parse_hex(hex)
+        } else if let Some((name, rest)) = value.split_once('(') {
+            rest.strip_suffix(')')
+                .and_then(|arguments| Self::parse_function(name.trim(), arguments))
+        } else {
+            named_color(&value)
+        };
+
+        color.ok_or_else(|| anyhow::anyhow!("Invalid color: '{}'", input))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// `255` or `100%`
+fn parse_channel(value: &str) -> Option<u8> {
+    let channel = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0 * 255.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    (0.0..=255.0)
+        .contains(&channel)
+        .then(|| channel.round() as u8)
+}
+
+/// `0.6` or `60%`
+fn parse_alpha(value: &str) -> Option<u8> {
+    let alpha = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    (0.0..=1.0)
+        .contains(&alpha)
+        .then(|| (alpha * 255.0).round() as u8)
+}
+
+/// `40%` as 0.4
+fn parse_percentage(value: &str) -> Option<f32> {
+    let fraction = value.strip_suffix('%')?.parse::<f32>().ok()? / 100.0;
+    (0.0..=1.0).contains(&fraction).then_some(fraction)
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    (channel(r), channel(g), channel(b))
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    if name == "transparent" {
+        return Some(Color::TRANSPARENT);
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, rgb)| Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+}
+
+/// CSS Color Module Level 4 named colors
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Color {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parses_every_supported_form() {
+        assert_eq!(parse("#fff"), Color::WHITE);
+        assert_eq!(parse("#FFFFFF"), Color::WHITE);
+        assert_eq!(parse("white"), Color::WHITE);
+        assert_eq!(parse("#00000080"), Color::rgba(0, 0, 0, 128));
+        assert_eq!(parse("#0008"), Color::rgba(0, 0, 0, 136));
+        assert_eq!(parse("rgba(0,0,0,0.6)"), Color::rgba(0, 0, 0, 153));
+        assert_eq!(parse("rgb(255 128 0 / 50%)"), Color::rgba(255, 128, 0, 128));
+        assert_eq!(parse(" RGB(100%, 0%, 0%) "), Color::rgb(255, 0, 0));
+        assert_eq!(parse("hsl(120, 100%, 25%)"), Color::rgb(0, 128, 0));
+        assert_eq!(
+            parse("hsla(0deg, 0%, 100%, 0)"),
+            Color::rgba(255, 255, 255, 0)
+        );
+        assert_eq!(parse("rebeccapurple"), Color::rgb(0x66, 0x33, 0x99));
+
+        for invalid in [
+            "",
+            "#12",
+            "#gggggg",
+            "rgb(1, 2)",
+            "rgb(300, 0, 0)",
+            "blurple",
+        ] {
+            assert!(invalid.parse::<Color>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_serializes_normalized_and_blends_alpha() {
+        assert_eq!(
+            serde_json::to_string(&parse("WHITE")).unwrap(),
+            "\"#ffffff\""
+        );
+        assert_eq!(parse("rgba(0, 0, 0, 0.5)").to_string(), "#00000080");
+        let round_trip: Color = serde_json::from_str("\"#00000080\"").unwrap();
+        assert_eq!(round_trip.a, 128);
+        assert!(serde_json::from_str::<Color>("\"nope\"").is_err());
+
+        let mut pixel = Rgb([255, 255, 255]);
+        parse("#00000080").blend(&mut pixel, 1.0);
+        assert_eq!(pixel, Rgb([127, 127, 127]));
+    }
+}
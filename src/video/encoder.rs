@@ -0,0 +1,377 @@
+use anyhow::Result;
+use ffmpeg_next as ffmpeg;
+use image::RgbImage;
+use std::path::Path;
+
+use super::audio::{AudioBuffer, AudioEncoder};
+use super::profile::{OutputProfile, RateControl};
+
+/// One video frame handed to the encode pipeline
+pub(crate) enum SourceFrame {
+    /// RGB image at the profile size, converted to YUV420P by the pipeline
+    Rgb(RgbImage),
+    /// Ready-made YUV420P frame at the profile size, e.g. from a decoder
+    Yuv(ffmpeg::frame::Video),
+    /// Show the previous frame again
+    Repeat,
+}
+
+/// Produces the picture for every frame of a video
+pub(crate) trait FrameSource {
+    /// Frame `index`, shown at `time` seconds
+    fn frame(&mut self, index: u32, time: f64) -> Result<SourceFrame>;
+}
+
+impl<F> FrameSource for F
+where
+    F: FnMut(u32, f64) -> Result<SourceFrame>,
+{
+    fn frame(&mut self, index: u32, time: f64) -> Result<SourceFrame> {
+        self(index, time)
+    }
+}
+
+/// H.264 (+ optional AAC) encoder shared by every template
+pub(crate) struct EncodePipeline<'a> {
+    profile: &'a OutputProfile,
+    duration_seconds: u32,
+    audio: Option<&'a AudioBuffer>,
+}
+
+impl<'a> EncodePipeline<'a> {
+    pub(crate) fn new(profile: &'a OutputProfile, duration_seconds: u32) -> Self {
+        Self {
+            profile,
+            duration_seconds,
+            audio: None,
+        }
+    }
+
+    /// Interleave `audio` with the video; it should already last `duration_seconds`
+    pub(crate) fn with_audio(mut self, audio: &'a AudioBuffer) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// Encode every frame of `source` into an MP4 at `output_path`
+    pub(crate) fn encode(&self, output_path: &Path, source: &mut dyn FrameSource) -> Result<()> {
+        let profile = self.profile;
+        let (width, height) = (profile.width, profile.height);
+        let frame_rate = profile.frame_rate as i32;
+
+        let mut output = ffmpeg::format::output(&output_path)?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
+
+        // Add video stream
+        let mut video_stream = output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::H264))?;
+        let video_context =
+            ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
+        let mut video_encoder = video_context.encoder().video()?;
+
+        // Configure video encoder
+        video_encoder.set_width(width);
+        video_encoder.set_height(height);
+        video_encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+        // Rendered and decoded frames alike are in video range
+        video_encoder.set_color_range(ffmpeg::color::Range::MPEG);
+        video_encoder.set_time_base((1, frame_rate)); // one tick per frame
+        video_encoder.set_frame_rate(Some((frame_rate, 1)));
+        video_encoder.set_gop(profile.gop); // GOP size
+        video_encoder.set_qmin(profile.qmin);
+        video_encoder.set_qmax(profile.qmax);
+
+        // CRF is an x264 private option, bitrate limits live on the codec context
+        let mut options = ffmpeg::Dictionary::new();
+        match profile.rate_control {
+            RateControl::Crf(crf) => options.set("crf", &crf.to_string()),
+            RateControl::Bitrate {
+                bit_rate,
+                max_bit_rate,
+            } => {
+                video_encoder.set_bit_rate(bit_rate);
+                video_encoder.set_max_bit_rate(max_bit_rate);
+            }
+        }
+
+        if global_header {
+            video_encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
+        }
+
+        let mut encoder =
+            video_encoder.open_as_with(ffmpeg::encoder::find(ffmpeg::codec::Id::H264), options)?;
+        video_stream.set_parameters(&encoder);
+        video_stream.set_time_base((1, frame_rate));
+        video_stream.set_avg_frame_rate((frame_rate, 1));
+
+        let encoder_time_base = encoder.time_base();
+
+        // Audio goes into the same output as the second stream
+        let mut audio = match self.audio {
+            Some(buffer) => Some((
+                AudioEncoder::add_stream(&mut output, global_header)?,
+                buffer,
+            )),
+            None => None,
+        };
+
+        // Write header
+        output.write_header()?;
+
+        // The muxer picks its own stream time base while writing the header
+        // (MP4 uses e.g. 1/15360), so it has to be read back afterwards
+        let stream_time_base = output
+            .stream(0)
+            .ok_or_else(|| anyhow::anyhow!("Video stream missing after header"))?
+            .time_base();
+        if let Some((audio_encoder, _)) = audio.as_mut() {
+            audio_encoder.header_written(&output)?;
+        }
+
+        let write_packets = |encoder: &mut ffmpeg::encoder::Video,
+                             output: &mut ffmpeg::format::context::Output|
+         -> Result<()> {
+            let mut encoded = ffmpeg::packet::Packet::empty();
+            while encoder.receive_packet(&mut encoded).is_ok() {
+                encoded.set_stream(0);
+                encoded.set_duration(1); // one frame in encoder time base
+                encoded.rescale_ts(encoder_time_base, stream_time_base);
+                encoded.write_interleaved(output)?;
+            }
+            Ok(())
+        };
+
+        let total_frames = profile.total_frames(self.duration_seconds);
+        let mut frame = None;
+
+        for i in 0..total_frames {
+            // A fresh frame is used whenever the content changes,
+            // so the encoder never sees a buffer being rewritten
+            match source.frame(i, profile.frame_time(i))? {
+                SourceFrame::Rgb(image) => frame = Some(rgb_to_yuv_frame(&image, profile)?),
+                SourceFrame::Yuv(yuv) => {
+                    if yuv.width() != width || yuv.height() != height {
+                        return Err(anyhow::anyhow!(
+                            "Frame {} is {}x{}, expected {}x{}",
+                            i,
+                            yuv.width(),
+                            yuv.height(),
+                            width,
+                            height
+                        ));
+                    }
+                    frame = Some(yuv);
+                }
+                SourceFrame::Repeat => {}
+            }
+            let frame = frame
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Frame source repeated before the first frame"))?;
+            frame.set_pts(Some(i as i64));
+
+            encoder.send_frame(frame)?;
+            write_packets(&mut encoder, &mut output)?;
+
+            // Keep the audio packets level with the video that has been sent so far
+            if let Some((audio_encoder, audio_buffer)) = audio.as_mut() {
+                audio_encoder.encode_until(audio_buffer, profile.frame_time(i + 1), &mut output)?;
+            }
+        }
+
+        // Flush encoder
+        encoder.send_eof()?;
+        write_packets(&mut encoder, &mut output)?;
+
+        if let Some((audio_encoder, audio_buffer)) = audio.as_mut() {
+            audio_encoder.finish(audio_buffer, &mut output)?;
+        }
+
+        output.write_trailer()?;
+
+        Ok(())
+    }
+}
+
+/// Convert RGB to video range (16 - 235) BT.601 YUV, the range decoded footage
+/// comes in and the stream is tagged with
+fn rgb_to_yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+
+    let y = 16.0 + 219.0 * (0.299 * r + 0.587 * g + 0.114 * b);
+    let u = 128.0 + 224.0 * (-0.169 * r - 0.331 * g + 0.5 * b);
+    let v = 128.0 + 224.0 * (0.5 * r - 0.419 * g - 0.081 * b);
+
+    let level = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    (level(y), level(u), level(v))
+}
+
+/// Convert an RGB image at the profile size into a new YUV420P frame
+fn rgb_to_yuv_frame(image: &RgbImage, profile: &OutputProfile) -> Result<ffmpeg::frame::Video> {
+    if image.width() != profile.width || image.height() != profile.height {
+        return Err(anyhow::anyhow!(
+            "Rendered image is {}x{}, expected {}x{}",
+            image.width(),
+            image.height(),
+            profile.width,
+            profile.height
+        ));
+    }
+
+    let mut frame = ffmpeg::frame::Video::new(
+        ffmpeg::format::Pixel::YUV420P,
+        profile.width,
+        profile.height,
+    );
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+
+    // Rows are padded for alignment, so planes are indexed by their stride, not the width
+    let y_stride = frame.stride(0);
+    let u_stride = frame.stride(1);
+    let v_stride = frame.stride(2);
+
+    unsafe {
+        let y_size = y_stride * height;
+        let u_size = u_stride * (height / 2);
+        let v_size = v_stride * (height / 2);
+
+        let y_plane = std::slice::from_raw_parts_mut(frame.data_mut(0).as_mut_ptr(), y_size);
+        let u_plane = std::slice::from_raw_parts_mut(frame.data_mut(1).as_mut_ptr(), u_size);
+        let v_plane = std::slice::from_raw_parts_mut(frame.data_mut(2).as_mut_ptr(), v_size);
+
+        // Convert RGB to YUV420P
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = image.get_pixel(x as u32, y as u32);
+                let (yuv_y, yuv_u, yuv_v) = rgb_to_yuv(pixel[0], pixel[1], pixel[2]);
+
+                // Y plane (full resolution)
+                y_plane[y * y_stride + x] = yuv_y;
+
+                // U and V planes (half resolution - 4:2:0 subsampling)
+                if x % 2 == 0 && y % 2 == 0 {
+                    u_plane[(y / 2) * u_stride + (x / 2)] = yuv_u;
+                    v_plane[(y / 2) * v_stride + (x / 2)] = yuv_v;
+                }
+            }
+        }
+    }
+
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_to_yuv_reference_colors() {
+        assert_eq!(rgb_to_yuv(0, 0, 0), (16, 128, 128));
+        assert_eq!(rgb_to_yuv(255, 255, 255), (235, 128, 128));
+
+        // Pure red is strong in V and weak in U
+        let (_, u, v) = rgb_to_yuv(255, 0, 0);
+        assert!(v > 200 && u < 128);
+    }
+
+    #[test]
+    fn test_encode_yuv_frames() {
+        ffmpeg::init().unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profile = OutputProfile {
+            width: 64,
+            height: 64,
+            ..OutputProfile::square()
+        };
+        let pipeline = EncodePipeline::new(&profile, 1);
+
+        let gray = |width, height| {
+            let mut frame =
+                ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, width, height);
+            for plane in 0..3 {
+                frame.data_mut(plane).fill(128);
+            }
+            frame
+        };
+        let mut source = |index: u32, _time: f64| -> Result<SourceFrame> {
+            Ok(match index {
+                0 => SourceFrame::Yuv(gray(64, 64)),
+                _ => SourceFrame::Repeat,
+            })
+        };
+        let output_path = temp_dir.path().join("yuv.mp4");
+        pipeline.encode(&output_path, &mut source).unwrap();
+        assert!(std::fs::metadata(&output_path).unwrap().len() > 0);
+
+        // Decoded frames are not scaled, so the size has to match the profile
+        let mut wrong_size = |_index: u32, _time: f64| Ok(SourceFrame::Yuv(gray(32, 32)));
+        let error = pipeline
+            .encode(&temp_dir.path().join("wrong.mp4"), &mut wrong_size)
+            .unwrap_err();
+        assert!(error.to_string().contains("32x32"), "{}", error);
+    }
+
+    #[test]
+    fn test_footage_levels_match_with_and_without_overlays() {
+        use crate::video::footage::{BackgroundVideo, Footage};
+
+        ffmpeg::init().unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profile = OutputProfile {
+            width: 64,
+            height: 64,
+            ..OutputProfile::square()
+        };
+        let encode_still = |name: &str, frame: ffmpeg::frame::Video| {
+            let path = temp_dir.path().join(name);
+            let mut frame = Some(frame);
+            let mut source = |_index: u32, _time: f64| -> Result<SourceFrame> {
+                Ok(frame.take().map_or(SourceFrame::Repeat, SourceFrame::Yuv))
+            };
+            EncodePipeline::new(&profile, 1)
+                .encode(&path, &mut source)
+                .unwrap();
+            let video = BackgroundVideo {
+                path: path.to_string_lossy().into_owned(),
+                start_seconds: 0.0,
+                end_seconds: None,
+                focus_x: 0.5,
+                focus_y: 0.5,
+            };
+            Footage::open(&video, 64, 64).unwrap()
+        };
+
+        // Footage of a gray ramp from video black to video white
+        let mut ramp = ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, 64, 64);
+        let stride = ramp.stride(0);
+        for y in 0..64 {
+            for x in 0..64 {
+                ramp.data_mut(0)[y * stride + x] = (16 + x * 219 / 63) as u8;
+            }
+        }
+        ramp.data_mut(1).fill(128);
+        ramp.data_mut(2).fill(128);
+        let mut footage = encode_still("footage.mp4", ramp);
+
+        // Without overlays the decoded frame is encoded as is, with them it
+        // goes through RGB first
+        let direct = footage.yuv_frame_at(0.0).unwrap();
+        let rgb = rgb_to_yuv_frame(&footage.frame_at(0.0).unwrap(), &profile).unwrap();
+        let direct = encode_still("direct.mp4", direct)
+            .yuv_frame_at(0.0)
+            .unwrap();
+        let rgb = encode_still("rgb.mp4", rgb).yuv_frame_at(0.0).unwrap();
+
+        for y in 0..64 {
+            for x in 0..64 {
+                let a = direct.data(0)[y * direct.stride(0) + x];
+                let b = rgb.data(0)[y * rgb.stride(0) + x];
+                assert!(a.abs_diff(b) <= 4, "luma at {},{}: {} vs {}", x, y, a, b);
+            }
+        }
+    }
+}
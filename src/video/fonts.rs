@@ -0,0 +1,267 @@
+use ab_glyph::{Font, FontArc, GlyphId};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Where a font in `VideoSpec::fonts` comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FontSource {
+    /// A .ttf or .otf file
+    Path(PathBuf),
+    /// Full name ("DejaVu Sans Bold") or family name ("DejaVu Sans") of a
+    /// font in the generator's font directory
+    Family(String),
+}
+
+/// Fonts tried in order for every character; the first one that has a glyph draws it
+#[derive(Clone)]
+pub struct FontChain {
+    fonts: Vec<FontArc>,
+}
+
+impl FontChain {
+    /// Chain of `fonts`, falling back to the embedded DejaVu Sans last
+    pub fn new(mut fonts: Vec<FontArc>) -> Self {
+        fonts.push(default_font());
+        Self { fonts }
+    }
+
+    /// The embedded DejaVu Sans Mono, for code, falling back to DejaVu Sans
+    pub(crate) fn monospace() -> Self {
+        static MONO: OnceLock<FontArc> = OnceLock::new();
+        let mono = MONO.get_or_init(|| {
+            FontArc::try_from_slice(include_bytes!("../assets/DejaVuSansMono.ttf"))
+                .expect("embedded font is valid")
+        });
+        Self::new(vec![mono.clone()])
+    }
+
+    /// Font used for metrics and for characters no font in the chain covers
+    pub fn primary(&self) -> &FontArc {
+        &self.fonts[0]
+    }
+
+    pub fn font(&self, index: usize) -> &FontArc {
+        &self.fonts[index]
+    }
+
+    /// Index of the font drawing `c` and its glyph there
+    pub fn glyph(&self, c: char) -> (usize, GlyphId) {
+        self.fonts
+            .iter()
+            .enumerate()
+            .map(|(index, font)| (index, font.glyph_id(c)))
+            .find(|(_, id)| id.0 != 0)
+            .unwrap_or((0, self.primary().glyph_id(c)))
+    }
+}
+
+impl Default for FontChain {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+/// The embedded DejaVu Sans, parsed on first use and shared after that
+fn default_font() -> FontArc {
+    static DEFAULT: OnceLock<FontArc> = OnceLock::new();
+    DEFAULT
+        .get_or_init(|| {
+            FontArc::try_from_slice(include_bytes!("../assets/DejaVuSans.ttf"))
+                .expect("embedded font is valid")
+        })
+        .clone()
+}
+
+/// Loads each font once and hands out chains built from them
+pub(crate) struct FontLibrary {
+    font_dir: Option<PathBuf>,
+    loaded: Mutex<HashMap<FontSource, FontArc>>,
+}
+
+impl FontLibrary {
+    pub(crate) fn new(font_dir: Option<PathBuf>) -> Self {
+        Self {
+            font_dir,
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Chain of `sources` in order, followed by the embedded default
+    pub(crate) fn chain(&self, sources: &[FontSource]) -> Result<FontChain> {
+        let fonts = sources
+            .iter()
+            .map(|source| self.load(source))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(FontChain::new(fonts))
+    }
+
+    fn load(&self, source: &FontSource) -> Result<FontArc> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(font) = loaded.get(source) {
+            return Ok(font.clone());
+        }
+
+        let path = match source {
+            FontSource::Path(path) => path.clone(),
+            FontSource::Family(family) => self.find_family(family)?,
+        };
+        let data = std::fs::read(&path)
+            .with_context(|| format!("Failed to read font {}", path.display()))?;
+        let font = FontArc::try_from_vec(data)
+            .map_err(|_| anyhow::anyhow!("Failed to load font {}", path.display()))?;
+
+        loaded.insert(source.clone(), font.clone());
+        Ok(font)
+    }
+
+    /// Font file in the font directory whose full name matches `family`,
+    /// otherwise the regular style of the family with that name
+    fn find_family(&self, family: &str) -> Result<PathBuf> {
+        let font_dir = self.font_dir.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Font family '{}' requested but no font directory is set",
+                family
+            )
+        })?;
+
+        let mut files = Vec::new();
+        collect_font_files(font_dir, &mut files)?;
+        files.sort();
+
+        let mut family_match = None;
+        for path in files {
+            let Some(names) = FontNames::read(&path) else {
+                continue;
+            };
+            if names
+                .full
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(family))
+            {
+                return Ok(path);
+            }
+            if names
+                .family
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(family))
+            {
+                // Keep the first match unless a regular style turns up later
+                let regular = names.is_regular();
+                match family_match {
+                    Some((_, true)) => {}
+                    Some((_, false)) if !regular => {}
+                    _ => family_match = Some((path, regular)),
+                }
+            }
+        }
+
+        family_match.map(|(path, _)| path).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Font family '{}' not found in {}",
+                family,
+                font_dir.display()
+            )
+        })
+    }
+}
+
+/// Names from a font's name table
+struct FontNames {
+    full: Vec<String>,
+    family: Vec<String>,
+    subfamily: Vec<String>,
+}
+
+impl FontNames {
+    fn read(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        let face = ttf_parser::Face::parse(&data, 0).ok()?;
+
+        let mut names = Self {
+            full: Vec::new(),
+            family: Vec::new(),
+            subfamily: Vec::new(),
+        };
+        for name in face.names() {
+            let Some(value) = name.to_string() else {
+                continue;
+            };
+            match name.name_id {
+                ttf_parser::name_id::FULL_NAME => names.full.push(value),
+                ttf_parser::name_id::FAMILY | ttf_parser::name_id::TYPOGRAPHIC_FAMILY => {
+                    names.family.push(value)
+                }
+                ttf_parser::name_id::SUBFAMILY | ttf_parser::name_id::TYPOGRAPHIC_SUBFAMILY => {
+                    names.subfamily.push(value)
+                }
+                _ => {}
+            }
+        }
+        Some(names)
+    }
+
+    fn is_regular(&self) -> bool {
+        self.subfamily.iter().any(|style| {
+            style.eq_ignore_ascii_case("regular") || style.eq_ignore_ascii_case("book")
+        })
+    }
+}
+
+/// All .ttf and .otf files below `dir`
+fn collect_font_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read font directory {}", dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_font_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("ttf") || extension.eq_ignore_ascii_case("otf")
+        }) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_family_lookup_and_caching() {
+        let font_dir = TempDir::new().unwrap();
+        std::fs::copy("src/assets/DejaVuSans.ttf", font_dir.path().join("a.ttf")).unwrap();
+
+        let library = FontLibrary::new(Some(font_dir.path().to_path_buf()));
+        let source = FontSource::Family("dejavu sans".to_string());
+        let chain = library.chain(std::slice::from_ref(&source)).unwrap();
+        assert_eq!(chain.glyph('A').0, 0);
+
+        // Served from the cache even once the file is gone
+        std::fs::remove_file(font_dir.path().join("a.ttf")).unwrap();
+        assert!(library.chain(&[source]).is_ok());
+        assert!(library
+            .chain(&[FontSource::Family("Missing Sans".to_string())])
+            .is_err());
+    }
+
+    #[test]
+    fn test_missing_glyphs_fall_back() {
+        let library = FontLibrary::new(None);
+        let mono = FontSource::Path(PathBuf::from("src/assets/DejaVuSansMono.ttf"));
+        let chain = library.chain(&[mono]).unwrap();
+
+        // Latin comes from the primary font, glyphs only DejaVu Sans has from the fallback
+        assert_eq!(chain.glyph('A').0, 0);
+        assert_eq!(chain.glyph('\u{01C4}').0, 1); // DZ digraph with caron
+
+        // Nothing covers it: the primary font's missing glyph box
+        assert_eq!(chain.glyph('\u{4E2D}'), (0, GlyphId(0)));
+    }
+}
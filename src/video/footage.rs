@@ -0,0 +1,532 @@
+use anyhow::{Context, Result};
+use ffmpeg_next as ffmpeg;
+use image::{Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::animation::{self, FrameRenderer, TitleAnimation};
+use super::encoder::{FrameSource, SourceFrame};
+
+/// Video file playing behind the text, trimmed to a segment and looped
+/// when the segment is shorter than the video being generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundVideo {
+    pub path: String,
+    #[serde(default)]
+    pub start_seconds: f64,
+    #[serde(default)]
+    pub end_seconds: Option<f64>, // end of the file when not set
+    /// Point kept in view when cropping to the output aspect, as fractions
+    /// of the footage size; 0.5/0.5 crops around the center
+    #[serde(default = "half")]
+    pub focus_x: f32,
+    #[serde(default = "half")]
+    pub focus_y: f32,
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+/// Decoded frame and the segment time it is shown from
+struct DecodedFrame {
+    time: f64,
+    frame: ffmpeg::frame::Video,
+}
+
+/// Decodes a `BackgroundVideo` into frames covering the output size
+pub(crate) struct Footage {
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::decoder::Video,
+    stream_index: usize,
+    time_base: f64,
+    origin: f64, // stream start time in seconds, timestamps count from here
+    start: f64,
+    length: f64,
+    focus: (f32, f32),
+    size: (u32, u32),
+    scaler: Option<(
+        ffmpeg::software::scaling::Context,
+        u32,
+        u32,
+        ffmpeg::format::Pixel,
+    )>,
+    eof: bool,
+    current: Option<DecodedFrame>,
+    pending: Option<DecodedFrame>,
+    image: Option<RgbImage>, // `current` converted, until it changes
+    yuv: Option<ffmpeg::frame::Video>, // the same as a YUV420P frame
+}
+
+impl Footage {
+    /// Open the video for frames of `width` x `height`
+    pub(crate) fn open(video: &BackgroundVideo, width: u32, height: u32) -> Result<Self> {
+        let path = Path::new(&video.path);
+        let input = ffmpeg::format::input(&path)
+            .with_context(|| format!("Failed to open background video {}", video.path))?;
+        let stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or_else(|| anyhow::anyhow!("No video stream found in {}", video.path))?;
+        let stream_index = stream.index();
+
+        let time_base = f64::from(stream.time_base());
+        let origin = match stream.start_time() {
+            i64::MIN => 0.0, // AV_NOPTS_VALUE
+            start => start as f64 * time_base,
+        };
+        let file_length = if stream.duration() > 0 {
+            stream.duration() as f64 * time_base
+        } else {
+            input.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64
+        };
+
+        let start = video.start_seconds.max(0.0);
+        let end = video.end_seconds.unwrap_or(file_length).min(file_length);
+        let length = end - start;
+        if length <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Background video {} has no footage between {}s and {}s",
+                video.path,
+                start,
+                end
+            ));
+        }
+
+        let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+        let decoder = context.decoder().video()?;
+
+        let mut footage = Self {
+            input,
+            decoder,
+            stream_index,
+            time_base,
+            origin,
+            start,
+            length,
+            focus: (video.focus_x, video.focus_y),
+            size: (width, height),
+            scaler: None,
+            eof: false,
+            current: None,
+            pending: None,
+            image: None,
+            yuv: None,
+        };
+        footage.rewind()?;
+        Ok(footage)
+    }
+
+    /// Frame shown `time` seconds into the generated video
+    pub(crate) fn frame_at(&mut self, time: f64) -> Result<RgbImage> {
+        self.seek_to(time)?;
+        if self.image.is_none() {
+            let (scaled, left, top) = self.scale_current(ffmpeg::format::Pixel::RGB24)?;
+            let (width, height) = self.size;
+            let stride = scaled.stride(0);
+            let data = scaled.data(0);
+            self.image = Some(RgbImage::from_fn(width, height, |x, y| {
+                let i = (top + y as usize) * stride + (left + x as usize) * 3;
+                Rgb([data[i], data[i + 1], data[i + 2]])
+            }));
+        }
+        Ok(self.image.clone().unwrap_or_default())
+    }
+
+    /// `frame_at` as a YUV420P frame, without a round trip through RGB
+    pub(crate) fn yuv_frame_at(&mut self, time: f64) -> Result<ffmpeg::frame::Video> {
+        self.seek_to(time)?;
+        if self.yuv.is_none() {
+            let (scaled, left, top) = self.scale_current(ffmpeg::format::Pixel::YUV420P)?;
+            let (width, height) = self.size;
+            let mut frame =
+                ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, width, height);
+            // Chroma planes are half size, so the crop starts on an even pixel
+            let (left, top) = (left & !1, top & !1);
+            for plane in 0..3 {
+                let shift = usize::from(plane > 0);
+                let (plane_width, plane_height) =
+                    (width as usize >> shift, height as usize >> shift);
+                let (source_stride, stride) = (scaled.stride(plane), frame.stride(plane));
+                let source = scaled.data(plane);
+                let target = frame.data_mut(plane);
+                for row in 0..plane_height {
+                    let start = ((top >> shift) + row) * source_stride + (left >> shift);
+                    target[row * stride..row * stride + plane_width]
+                        .copy_from_slice(&source[start..start + plane_width]);
+                }
+            }
+            self.yuv = Some(frame);
+        }
+        self.yuv
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Background video has no frames"))
+    }
+
+    /// Decode up to the frame shown `time` seconds into the generated video
+    fn seek_to(&mut self, time: f64) -> Result<()> {
+        let target = self.start + time.max(0.0) % self.length;
+
+        // Looped around: start decoding the segment again
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| current.time > target)
+        {
+            self.rewind()?;
+        }
+
+        // Keep the last frame at or before the target, or the first one after
+        // a seek that landed past it
+        loop {
+            if self.pending.is_none() {
+                self.pending = self.decode_next()?;
+            }
+            match &self.pending {
+                Some(next) if next.time <= target || self.current.is_none() => {
+                    self.current = self.pending.take();
+                    self.image = None;
+                    self.yuv = None;
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Seek to the start of the segment and drop everything decoded so far
+    fn rewind(&mut self) -> Result<()> {
+        let position = ((self.origin + self.start) * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        self.input.seek(position, ..position)?;
+        self.decoder.flush();
+        self.eof = false;
+        self.current = None;
+        self.pending = None;
+        self.image = None;
+        self.yuv = None;
+        Ok(())
+    }
+
+    /// Next frame of the video stream, `None` at the end of the file
+    fn decode_next(&mut self) -> Result<Option<DecodedFrame>> {
+        loop {
+            let mut frame = ffmpeg::frame::Video::empty();
+            if self.decoder.receive_frame(&mut frame).is_ok() {
+                let timestamp = frame.timestamp().unwrap_or(0);
+                let time = timestamp as f64 * self.time_base - self.origin;
+                return Ok(Some(DecodedFrame { time, frame }));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+
+            let mut packet = ffmpeg::Packet::empty();
+            match packet.read(&mut self.input) {
+                Ok(()) => {
+                    if packet.stream() == self.stream_index {
+                        self.decoder.send_packet(&packet)?;
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => {
+                    self.decoder.send_eof()?;
+                    self.eof = true;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Scale the current frame to cover the output size in `format`, with
+    /// the crop offset that keeps the focus point in view
+    fn scale_current(
+        &mut self,
+        format: ffmpeg::format::Pixel,
+    ) -> Result<(ffmpeg::frame::Video, usize, usize)> {
+        let frame = &self
+            .current
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Background video has no frames"))?
+            .frame;
+        let (width, height) = self.size;
+        let (source_width, source_height) = (frame.width(), frame.height());
+        let scale = (width as f32 / source_width as f32).max(height as f32 / source_height as f32);
+        let scaled_width = ((source_width as f32 * scale).ceil() as u32).max(width);
+        let scaled_height = ((source_height as f32 * scale).ceil() as u32).max(height);
+
+        // Rebuilt only if the footage changes size mid-stream
+        let stale = !matches!(&self.scaler, Some((_, w, h, f))
+            if (*w, *h, *f) == (source_width, source_height, format));
+        if stale {
+            let scaler = ffmpeg::software::scaling::Context::get(
+                frame.format(),
+                source_width,
+                source_height,
+                format,
+                scaled_width,
+                scaled_height,
+                ffmpeg::software::scaling::Flags::BILINEAR,
+            )?;
+            self.scaler = Some((scaler, source_width, source_height, format));
+        }
+        let Some((scaler, _, _, _)) = self.scaler.as_mut() else {
+            return Err(anyhow::anyhow!("Background video scaler missing"));
+        };
+
+        let mut scaled = ffmpeg::frame::Video::empty();
+        scaler.run(frame, &mut scaled)?;
+
+        let left = crop_offset(scaled_width, width, self.focus.0) as usize;
+        let top = crop_offset(scaled_height, height, self.focus.1) as usize;
+        Ok((scaled, left, top))
+    }
+}
+
+/// Start of the `visible` window in a `scaled` dimension, centered on
+/// `focus` but never past either edge
+fn crop_offset(scaled: u32, visible: u32, focus: f32) -> u32 {
+    let overflow = scaled.saturating_sub(visible) as f32;
+    let offset = focus.clamp(0.0, 1.0) * scaled as f32 - visible as f32 / 2.0;
+    offset.clamp(0.0, overflow).round() as u32
+}
+
+/// Text drawn once and composited onto moving footage.
+///
+/// The text is rendered on black and on white; the difference between the
+/// two is how much of the background each pixel lets through, so blended
+/// edges, shadows and translucent boxes survive compositing.
+pub(crate) struct Overlay {
+    on_black: RgbImage,
+    on_white: RgbImage,
+}
+
+impl Overlay {
+    /// Capture what `draw` paints on a `width` x `height` frame, with its result
+    pub(crate) fn capture<T>(
+        width: u32,
+        height: u32,
+        mut draw: impl FnMut(&mut RgbImage) -> Result<T>,
+    ) -> Result<(Self, T)> {
+        let mut on_black = RgbImage::new(width, height);
+        let result = draw(&mut on_black)?;
+        let mut on_white = RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+        draw(&mut on_white)?;
+
+        Ok((Self { on_black, on_white }, result))
+    }
+
+    /// Draw the captured text over `frame` at `opacity` (0.0 - 1.0)
+    pub(crate) fn composite(&self, frame: &mut RgbImage, opacity: f32) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        for ((pixel, black), white) in frame
+            .pixels_mut()
+            .zip(self.on_black.pixels())
+            .zip(self.on_white.pixels())
+        {
+            for c in 0..3 {
+                // black = text * alpha, white - black = 255 * (1 - alpha)
+                let coverage = 1.0 - (white[c] as f32 - black[c] as f32) / 255.0;
+                let value =
+                    pixel[c] as f32 * (1.0 - coverage * opacity) + black[c] as f32 * opacity;
+                pixel[c] = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    /// `composite` onto a YUV420P frame of decoded footage. Compositing is
+    /// linear, so blending the text's YUV values gives the same picture as
+    /// blending in RGB; the text is converted to video range (16 - 235) like
+    /// the footage, and its coverage is averaged over each 2x2 chroma block.
+    pub(crate) fn composite_yuv(&self, frame: &mut ffmpeg::frame::Video, opacity: f32) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let (width, height) = self.on_black.dimensions();
+        // Coverage and premultiplied text color of a pixel
+        let sample = |x: u32, y: u32| {
+            let black = self.on_black.get_pixel(x, y);
+            let white = self.on_white.get_pixel(x, y);
+            let coverage = (0..3)
+                .map(|c| 1.0 - (white[c] as f32 - black[c] as f32) / 255.0)
+                .sum::<f32>()
+                / 3.0;
+            (coverage, black.0.map(|c| c as f32))
+        };
+
+        let stride = frame.stride(0);
+        let luma = frame.data_mut(0);
+        for y in 0..height {
+            for x in 0..width {
+                let (coverage, [r, g, b]) = sample(x, y);
+                let text = 16.0 * coverage + 219.0 / 255.0 * (0.299 * r + 0.587 * g + 0.114 * b);
+                let pixel = &mut luma[y as usize * stride + x as usize];
+                let value = *pixel as f32 * (1.0 - coverage * opacity) + text * opacity;
+                *pixel = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+
+        let (u_stride, v_stride) = (frame.stride(1), frame.stride(2));
+        for y in 0..height / 2 {
+            for x in 0..width / 2 {
+                let (mut coverage, mut r, mut g, mut b) = (0.0, 0.0, 0.0, 0.0);
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let (c, [sr, sg, sb]) = sample(x * 2 + dx, y * 2 + dy);
+                    coverage += c / 4.0;
+                    r += sr / 4.0;
+                    g += sg / 4.0;
+                    b += sb / 4.0;
+                }
+                let u = 128.0 * coverage + 224.0 / 255.0 * (-0.169 * r - 0.331 * g + 0.5 * b);
+                let v = 128.0 * coverage + 224.0 / 255.0 * (0.5 * r - 0.419 * g - 0.081 * b);
+                for (plane, stride, text) in [(1, u_stride, u), (2, v_stride, v)] {
+                    let pixel = &mut frame.data_mut(plane)[y as usize * stride + x as usize];
+                    let value = *pixel as f32 * (1.0 - coverage * opacity) + text * opacity;
+                    *pixel = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Footage with the title composited on every frame, animated like a still background
+pub(crate) struct FootageRenderer<F> {
+    footage: Footage,
+    animation: Option<TitleAnimation>,
+    duration_seconds: f32,
+    draw: F,
+    overlay: Overlay,
+    total_chars: usize,
+    visible_chars: usize,
+    yuv: bool,
+}
+
+impl<F> FootageRenderer<F>
+where
+    F: FnMut(&mut RgbImage, usize) -> Result<usize>,
+{
+    /// `draw` paints the text with only the first `visible_chars` characters
+    /// and returns how many characters there are in total
+    pub(crate) fn new(
+        footage: Footage,
+        animation: Option<TitleAnimation>,
+        duration_seconds: f32,
+        mut draw: F,
+    ) -> Result<Self> {
+        let (width, height) = footage.size;
+        let (overlay, total_chars) =
+            Overlay::capture(width, height, |image| draw(image, usize::MAX))?;
+
+        Ok(Self {
+            footage,
+            animation,
+            duration_seconds,
+            draw,
+            overlay,
+            total_chars,
+            visible_chars: usize::MAX,
+            yuv: false,
+        })
+    }
+
+    /// Hand the footage on as YUV frames, for when nothing else is drawn
+    /// over it; Ken Burns still zooms in RGB
+    pub(crate) fn yuv(mut self) -> Self {
+        self.yuv = true;
+        self
+    }
+
+    /// Text opacity at `time`, re-capturing the text as it is typed
+    fn update_overlay(&mut self, time: f64) -> Result<f32> {
+        match self.animation {
+            Some(TitleAnimation::FadeIn { seconds }) => Ok(animation::fade_progress(time, seconds)),
+            Some(TitleAnimation::Typewriter {
+                characters_per_second,
+            }) => {
+                let visible = animation::typed_chars(time, characters_per_second, self.total_chars);
+                if visible != self.visible_chars {
+                    let (width, height) = self.footage.size;
+                    let draw = &mut self.draw;
+                    self.overlay = Overlay::capture(width, height, |image| draw(image, visible))?.0;
+                    self.visible_chars = visible;
+                }
+                Ok(1.0)
+            }
+            None | Some(TitleAnimation::KenBurns { .. }) => Ok(1.0),
+        }
+    }
+}
+
+impl<F> FrameRenderer for FootageRenderer<F>
+where
+    F: FnMut(&mut RgbImage, usize) -> Result<usize>,
+{
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let mut frame = self.footage.frame_at(time)?;
+        let opacity = self.update_overlay(time)?;
+        if let Some(TitleAnimation::KenBurns { zoom }) = self.animation {
+            let scale = animation::ken_burns_scale(time, zoom, self.duration_seconds);
+            frame = animation::zoom_center(&frame, scale);
+        }
+
+        self.overlay.composite(&mut frame, opacity);
+        Ok(Some(frame))
+    }
+}
+
+impl<F> FrameSource for FootageRenderer<F>
+where
+    F: FnMut(&mut RgbImage, usize) -> Result<usize>,
+{
+    fn frame(&mut self, _index: u32, time: f64) -> Result<SourceFrame> {
+        if !self.yuv || matches!(self.animation, Some(TitleAnimation::KenBurns { .. })) {
+            let frame = self.render(time)?;
+            return Ok(frame.map_or(SourceFrame::Repeat, SourceFrame::Rgb));
+        }
+
+        let mut frame = self.footage.yuv_frame_at(time)?;
+        let opacity = self.update_overlay(time)?;
+        self.overlay.composite_yuv(&mut frame, opacity);
+        Ok(SourceFrame::Yuv(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_follows_focus_within_bounds() {
+        // 1920 wide footage cropped to a 1080 wide portrait frame
+        assert_eq!(crop_offset(1920, 1080, 0.5), 420);
+        assert_eq!(crop_offset(1920, 1080, 0.0), 0);
+        assert_eq!(crop_offset(1920, 1080, 1.0), 840);
+        assert_eq!(crop_offset(1920, 1080, 0.6), 612);
+        // Nothing to crop
+        assert_eq!(crop_offset(1080, 1080, 0.9), 0);
+    }
+
+    #[test]
+    fn test_overlay_composites_like_drawing_directly() {
+        let draw = |image: &mut RgbImage| -> Result<()> {
+            image.put_pixel(1, 0, Rgb([255, 255, 255]));
+            let translucent: crate::video::Color = "#ff000080".parse()?;
+            translucent.blend(image.get_pixel_mut(2, 0), 1.0);
+            Ok(())
+        };
+
+        let background = RgbImage::from_fn(3, 1, |x, _| Rgb([0, 40 * x as u8, 200]));
+        let mut expected = background.clone();
+        draw(&mut expected).unwrap();
+
+        let (overlay, ()) = Overlay::capture(3, 1, draw).unwrap();
+        let mut composited = background.clone();
+        overlay.composite(&mut composited, 1.0);
+        for (a, b) in composited.pixels().zip(expected.pixels()) {
+            for c in 0..3 {
+                assert!((a[c] as i32 - b[c] as i32).abs() <= 1, "{:?} vs {:?}", a, b);
+            }
+        }
+
+        // Fully transparent overlay leaves the footage untouched
+        let mut faded = background.clone();
+        overlay.composite(&mut faded, 0.0);
+        assert_eq!(faded, background);
+    }
+}
@@ -1,414 +1,1200 @@
-use ab_glyph::{FontRef, PxScale};
 use anyhow::Result;
 use ffmpeg_next as ffmpeg;
-use image::{ImageBuffer, Rgb, RgbImage};
-use imageproc::drawing::draw_text_mut;
+use image::{ImageBuffer, RgbImage};
 use std::path::{Path, PathBuf};
 
-use super::VideoSpec;
+use super::animation::{
+    self, FadeIn, FrameRenderer, KenBurns, RendererSource, StillFrame, TitleAnimation, Typewriter,
+};
+use super::audio;
+use super::background::{self, Background, ImageFit};
+use super::captions::CaptionTrack;
+use super::chart::{Chart, ChartData, ChartKind, ChartRenderer};
+use super::code::{CodeRenderer, CodeWindow};
+use super::encoder::{EncodePipeline, FrameSource, SourceFrame};
+use super::fonts::{FontChain, FontLibrary};
+use super::footage::{Footage, FootageRenderer};
+use super::layout::{LayoutReport, TextBox};
+use super::list::{self, ListItem, ListLayout, ListMode};
+use super::loudness::LoudnessReport;
+use super::overlays::{FrameOverlay, OverlaidSource};
+use super::progress::{CountdownLayer, ProgressBarLayer};
+use super::slideshow::{SlideFrame, SlideTimeline};
+use super::text::{self, TextLayout, TextLayoutOptions};
+use super::visualizer::{VisualizerRenderer, VisualizerStyle};
+use super::watermark::WatermarkLayer;
+use super::{Countdown, SlideTransition, VideoSpec, Watermark};
+
+/// Outcome of a generation run beyond the file itself
+#[derive(Debug, Clone)]
+pub struct GenerationReport {
+    pub output_path: PathBuf,
+    pub loudness: Option<LoudnessReport>, // set when the audio was normalized
+}
 
 pub struct VideoGenerator {
     output_dir: PathBuf,
-    temp_dir: PathBuf,
+    fonts: FontLibrary, // loaded once, shared by every video this generator renders
+    watermark: Option<Watermark>, // brand logo for specs without their own
 }
 
 impl VideoGenerator {
+    /// Generator writing videos to `output_dir`. `temp_dir` is still created
+    /// for existing callers, but nothing is written there now that audio is
+    /// muxed in-process.
     pub fn new(output_dir: impl Into<PathBuf>, temp_dir: impl Into<PathBuf>) -> Result<Self> {
         ffmpeg::init()?;
 
         let output_dir = output_dir.into();
-        let temp_dir = temp_dir.into();
 
         std::fs::create_dir_all(&output_dir)?;
-        std::fs::create_dir_all(&temp_dir)?;
+        std::fs::create_dir_all(temp_dir.into())?;
 
         Ok(Self {
             output_dir,
-            temp_dir,
+            fonts: FontLibrary::new(None),
+            watermark: None,
         })
     }
 
-    /// Parse hex color string to RGB values
-    fn parse_hex_color(hex: &str) -> Result<(u8, u8, u8)> {
-        let hex = hex.trim_start_matches('#');
-        if hex.len() != 6 {
-            return Err(anyhow::anyhow!("Invalid hex color format: {}", hex));
+    /// Look up `FontSource::Family` names in `font_dir` and its subdirectories
+    pub fn with_font_dir(mut self, font_dir: impl Into<PathBuf>) -> Self {
+        self.fonts = FontLibrary::new(Some(font_dir.into()));
+        self
+    }
+
+    /// Brand logo drawn on every video whose spec doesn't set a `watermark`
+    pub fn with_watermark(mut self, watermark: Watermark) -> Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    /// Fallback chain for the spec's fonts, ending with the embedded DejaVu Sans
+    fn load_fonts(&self, spec: &VideoSpec) -> Result<FontChain> {
+        self.fonts.chain(&spec.fonts)
+    }
+
+    /// Wrap `text` to `area_width` minus the layout margin on each side
+    fn layout_for_area(
+        fonts: &FontChain,
+        text: &str,
+        font_size: f32,
+        area_width: f32,
+        max_height: f32,
+        options: &TextLayoutOptions,
+    ) -> TextLayout {
+        let max_width = (area_width - options.margin as f32 * 2.0).max(1.0);
+        text::layout_text(fonts, text, font_size, max_width, max_height, options)
+    }
+
+    /// Part of the frame text may use, as (left, top, right, bottom) in px
+    fn text_area(spec: &VideoSpec) -> (f32, f32, f32, f32) {
+        let profile = &spec.output_profile;
+        profile.safe_area.bounds(profile.width, profile.height)
+    }
+
+    /// The spec's background at the profile size, solid `background_color` unless
+    /// a gradient or image is set
+    fn create_background(&self, spec: &VideoSpec) -> Result<RgbImage> {
+        let (width, height) = (spec.output_profile.width, spec.output_profile.height);
+        match &spec.background {
+            Some(background) => background.render(width, height, spec.background_color),
+            None => Ok(ImageBuffer::from_pixel(
+                width,
+                height,
+                spec.background_color.to_rgb(),
+            )),
         }
+    }
 
-        let r = u8::from_str_radix(&hex[0..2], 16)?;
-        let g = u8::from_str_radix(&hex[2..4], 16)?;
-        let b = u8::from_str_radix(&hex[4..6], 16)?;
+    /// Render `text` centered on the spec's background color
+    fn create_text_slide(&self, spec: &VideoSpec, text: &str) -> Result<RgbImage> {
+        let mut image = self.create_background(spec)?;
+        self.draw_text_block(&mut image, spec, text, usize::MAX)?;
+        Ok(image)
+    }
+
+    /// Wrap `text` and center it in the safe area
+    fn place_text_block(fonts: &FontChain, spec: &VideoSpec, text: &str) -> PlacedText {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let max_height = (bottom - top - spec.text_layout.margin as f32 * 2.0).max(0.0);
+        let layout = Self::layout_for_area(
+            fonts,
+            text,
+            spec.font_size as f32,
+            right - left,
+            max_height,
+            &spec.text_layout,
+        );
+
+        // Center the wrapped block both horizontally and vertically
+        PlacedText {
+            center_x: (left + right) / 2.0,
+            top: (top + bottom - layout.height()) / 2.0,
+            layout,
+        }
+    }
 
-        Ok((r, g, b))
+    /// Draw `text` wrapped and centered in the safe area, showing only the first
+    /// `visible_chars` characters. Returns the number of characters in the block.
+    fn draw_text_block(
+        &self,
+        image: &mut RgbImage,
+        spec: &VideoSpec,
+        text: &str,
+        visible_chars: usize,
+    ) -> Result<usize> {
+        let fonts = self.load_fonts(spec)?;
+        let block = Self::place_text_block(&fonts, spec, text);
+        block.draw(image, &fonts, spec, visible_chars);
+        Ok(block.layout.char_count())
     }
 
-    /// Convert RGB to YUV color space
-    fn rgb_to_yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
-        let r = r as f32 / 255.0;
-        let g = g as f32 / 255.0;
-        let b = b as f32 / 255.0;
+    /// Scale an image file to fit the frame, letterboxed on the spec's background
+    fn create_image_slide(&self, spec: &VideoSpec, path: &Path) -> Result<RgbImage> {
+        let mut image = self.create_background(spec)?;
+
+        let source = image::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to load slide image {}: {}", path.display(), e))?
+            .to_rgb8();
 
-        let y = (0.299 * r + 0.587 * g + 0.114 * b).max(0.0).min(1.0);
-        let u = (-0.169 * r - 0.331 * g + 0.5 * b + 0.5).max(0.0).min(1.0);
-        let v = (0.5 * r - 0.419 * g - 0.081 * b + 0.5).max(0.0).min(1.0);
+        // Contain: scale so the whole image is visible
+        background::place_image(&mut image, &source, ImageFit::Contain);
 
-        ((y * 255.0) as u8, (u * 255.0) as u8, (v * 255.0) as u8)
+        Ok(image)
     }
 
-    /// Create a text overlay image
-    fn create_text_overlay(&self, spec: &VideoSpec) -> Result<RgbImage> {
-        let width = 1080u32;
-        let height = 1920u32;
+    /// Image slides are loaded from disk, anything else is text
+    fn create_slide_image(&self, spec: &VideoSpec, slide: &str) -> Result<RgbImage> {
+        if is_image_slide(slide) {
+            self.create_image_slide(spec, Path::new(slide))
+        } else {
+            self.create_text_slide(spec, slide)
+        }
+    }
 
-        // Parse background color
-        let (bg_r, bg_g, bg_b) = Self::parse_hex_color(&spec.background_color)?;
-        let background_color = Rgb([bg_r, bg_g, bg_b]);
+    /// Stack headline, accent bar and optional subtitle in the middle of the safe area
+    fn place_title_card(fonts: &FontChain, spec: &VideoSpec) -> TitleCardLayout {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let (area_width, area_height) = (right - left, bottom - top);
+
+        // Headline is drawn larger than the base font size, subtitle smaller
+        let headline_size = spec.font_size as f32 * 1.5;
+        let subtitle_size = (spec.font_size as f32 * 0.6).max(20.0);
+
+        let bar_width = (area_width * 0.4) as u32;
+        let bar_height = (spec.font_size / 6).max(8);
+        let gap = spec.font_size as f32 * 0.5;
+
+        // Headline may use half the safe area, the subtitle a quarter
+        let headline = Self::layout_for_area(
+            fonts,
+            &spec.title,
+            headline_size,
+            area_width,
+            area_height / 2.0,
+            &spec.text_layout,
+        );
+        let subtitle = spec.subtitle.as_ref().map(|subtitle| {
+            Self::layout_for_area(
+                fonts,
+                subtitle,
+                subtitle_size,
+                area_width,
+                area_height / 4.0,
+                &spec.text_layout,
+            )
+        });
+
+        // Center the whole block vertically
+        let subtitle_height = subtitle
+            .as_ref()
+            .map(|layout| gap + layout.height())
+            .unwrap_or(0.0);
+        let block_height = headline.height() + gap + bar_height as f32 + subtitle_height;
+        let center_x = (left + right) / 2.0;
+        let mut y = (top + bottom - block_height) / 2.0;
+
+        let headline_top = y;
+        y += headline.height() + gap;
+        let bar = (
+            (center_x - bar_width as f32 / 2.0).max(0.0) as u32,
+            y.max(0.0) as u32,
+            bar_width,
+            bar_height,
+        );
+        y += bar_height as f32 + gap;
+
+        TitleCardLayout {
+            headline: PlacedText {
+                layout: headline,
+                center_x,
+                top: headline_top,
+            },
+            bar,
+            subtitle: subtitle.map(|layout| PlacedText {
+                layout,
+                center_x,
+                top: y,
+            }),
+        }
+    }
 
-        // Parse text color
-        let (text_r, text_g, text_b) = Self::parse_hex_color(&spec.text_color)?;
-        let text_color = Rgb([text_r, text_g, text_b]);
+    /// Stack opening quote mark, quote text, closing mark and attribution in the
+    /// middle of the safe area. The quote starts a quarter larger than the font
+    /// size and shrinks to fit; it is an error when it doesn't fit at the minimum size.
+    fn place_quote(
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        text: &str,
+        author: Option<&str>,
+    ) -> Result<QuoteLayout> {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let (area_width, area_height) = (right - left, bottom - top);
+
+        // The quote may use 60% of the safe area, the marks and attribution the rest
+        let max_height = area_height * 0.6;
+        let options = TextLayoutOptions {
+            shrink_to_fit: true,
+            ..spec.text_layout.clone()
+        };
+        let quote = Self::layout_for_area(
+            fonts,
+            text.trim(),
+            spec.font_size as f32 * 1.25,
+            area_width,
+            max_height,
+            &options,
+        );
+        let too_many_lines = options
+            .max_lines
+            .is_some_and(|max_lines| quote.lines.len() > max_lines);
+        if quote.height() > max_height || too_many_lines {
+            return Err(anyhow::anyhow!(
+                "Quote of {} characters doesn't fit: at {:.0}px it wraps to {} lines, \
+                 {:.0}px tall, but {:.0}px are available; shorten the quote or lower \
+                 text_layout.min_font_size",
+                text.trim().chars().count(),
+                quote.font_size,
+                quote.lines.len(),
+                quote.height(),
+                max_height
+            ));
+        }
 
-        // Create image with background color
-        let mut image = ImageBuffer::from_pixel(width, height, background_color);
+        let author = author
+            .filter(|author| !author.trim().is_empty())
+            .map(|author| {
+                Self::layout_for_area(
+                    fonts,
+                    &format!("\u{2014} {}", author.trim()),
+                    (spec.font_size as f32 * 0.6).max(20.0),
+                    area_width,
+                    area_height / 8.0,
+                    &spec.text_layout,
+                )
+            });
+
+        // Marks are three times the size of the quote, flush with its edges
+        let mark_size = quote.font_size * 3.0;
+        let open = text::ink_bounds(fonts, mark_size, "\u{201C}");
+        let close = text::ink_bounds(fonts, mark_size, "\u{201D}");
+        let gap = quote.font_size * 0.5;
+
+        let author_height = author.as_ref().map_or(0.0, |layout| gap + layout.height());
+        let block_height =
+            (open.3 - open.1) + gap + quote.height() + gap + (close.3 - close.1) + author_height;
+        let center_x = (left + right) / 2.0;
+        let quote_left = center_x - quote.width() / 2.0;
+        let quote_right = center_x + quote.width() / 2.0;
+        let mut y = (top + bottom - block_height) / 2.0;
+
+        let open_mark = (quote_left - open.0, y - open.1);
+        y += open.3 - open.1 + gap;
+        let quote_top = y;
+        y += quote.height() + gap;
+        let close_mark = (quote_right - close.2, y - close.1);
+        y += close.3 - close.1 + gap;
+
+        Ok(QuoteLayout {
+            mark_size,
+            open_mark,
+            close_mark,
+            quote: PlacedText {
+                layout: quote,
+                center_x,
+                top: quote_top,
+            },
+            // Right-aligned with the quote, unless that would push it past the left edge
+            author: author.map(|layout| PlacedText {
+                center_x: (quote_right - layout.width() / 2.0).max(left + layout.width() / 2.0),
+                top: y,
+                layout,
+            }),
+        })
+    }
 
-        // Load default font (we'll use a simple built-in font for now)
-        // In production, you'd load a proper TTF font file
-        let font_data = include_bytes!("../assets/DejaVuSans.ttf");
-        let font = FontRef::try_from_slice(font_data)
-            .map_err(|_| anyhow::anyhow!("Failed to load font"))?;
+    /// Heading pinned to the top of the safe area and the list centered below it
+    fn place_list(
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        heading: &str,
+        items: &[ListItem],
+        mode: ListMode,
+    ) -> Result<(Option<PlacedText>, ListLayout)> {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let margin = spec.text_layout.margin as f32;
+
+        let heading = (!heading.trim().is_empty()).then(|| {
+            let layout = Self::layout_for_area(
+                fonts,
+                heading,
+                spec.font_size as f32 * 1.2,
+                right - left,
+                (bottom - top) / 4.0,
+                &spec.text_layout,
+            );
+            PlacedText {
+                layout,
+                center_x: (left + right) / 2.0,
+                top: top + margin,
+            }
+        });
+        let list_top = heading.as_ref().map_or(top + margin, |heading| {
+            heading.top + heading.layout.height() + margin
+        });
+
+        let list = ListLayout::new(
+            fonts,
+            items,
+            mode,
+            (left + margin, list_top, right - margin, bottom - margin),
+            spec.font_size as f32,
+            &spec.text_layout,
+        )?;
+        Ok((heading, list))
+    }
 
-        let scale = PxScale::from(spec.font_size as f32);
+    /// Title above a code window as wide as the safe area, centered together.
+    /// The code is scaled from 3/4 of the font size down to the layout's minimum.
+    fn place_code_snippet(
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        language: &str,
+        code: &str,
+    ) -> Result<(Option<PlacedText>, CodeWindow)> {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let margin = spec.text_layout.margin as f32;
+        let center_x = (left + right) / 2.0;
+
+        let title = (!spec.title.trim().is_empty()).then(|| {
+            let layout = Self::layout_for_area(
+                fonts,
+                &spec.title,
+                spec.font_size as f32,
+                right - left,
+                (bottom - top) / 4.0,
+                &spec.text_layout,
+            );
+            PlacedText {
+                layout,
+                center_x,
+                top: top + margin,
+            }
+        });
+        let code_top = title.as_ref().map_or(top + margin, |title| {
+            title.top + title.layout.height() + margin
+        });
+
+        let font_size = spec.font_size as f32 * 0.75;
+        let mut window = CodeWindow::new(
+            language,
+            code,
+            (left + margin, code_top, right - margin, bottom - margin),
+            font_size,
+            spec.text_layout.min_font_size as f32,
+        )?;
+
+        // Center title and window together when the code leaves room
+        let slack = (bottom - margin - window.text_box().bottom).max(0.0) / 2.0;
+        window.offset(slack);
+        let title = title.map(|title| PlacedText {
+            top: title.top + slack,
+            ..title
+        });
+        Ok((title, window))
+    }
 
-        // Calculate text positioning (center horizontally, vertically centered)
-        // For simplicity, we'll estimate text width and center it
-        let estimated_char_width = spec.font_size as f32 * 0.6; // Rough estimation
-        let text_width = spec.title.len() as f32 * estimated_char_width;
+    /// Title above a chart filling the rest of the safe area, its labels at
+    /// half the font size
+    fn place_chart(
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        kind: ChartKind,
+        data: &ChartData,
+    ) -> Result<(Option<PlacedText>, Chart)> {
+        let table = data.load()?;
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let margin = spec.text_layout.margin as f32;
+
+        let title = (!spec.title.trim().is_empty()).then(|| {
+            let layout = Self::layout_for_area(
+                fonts,
+                &spec.title,
+                spec.font_size as f32,
+                right - left,
+                (bottom - top) / 4.0,
+                &spec.text_layout,
+            );
+            PlacedText {
+                layout,
+                center_x: (left + right) / 2.0,
+                top: top + margin,
+            }
+        });
+        let chart_top = title.as_ref().map_or(top + margin, |title| {
+            title.top + title.layout.height() + margin
+        });
+
+        let label_size = (spec.font_size as f32 * 0.5).max(spec.text_layout.min_font_size as f32);
+        let chart = Chart::new(
+            kind,
+            table,
+            fonts.clone(),
+            (left + margin, chart_top, right - margin, bottom - margin),
+            label_size,
+            spec.text_color,
+            spec.accent_color.unwrap_or(spec.text_color),
+        )?;
+        Ok((title, chart))
+    }
 
-        let x = ((width as f32 - text_width) / 2.0).max(50.0) as i32;
-        let y = (height as f32 / 2.0 - spec.font_size as f32 / 2.0) as i32;
+    /// Title above a band for the audio visualizer as wide as the safe area
+    /// and at most half as tall, centered together
+    fn place_visualizer(
+        fonts: &FontChain,
+        spec: &VideoSpec,
+    ) -> (Option<PlacedText>, (f32, f32, f32, f32)) {
+        let (left, top, right, bottom) = Self::text_area(spec);
+        let margin = spec.text_layout.margin as f32;
+
+        let title = (!spec.title.trim().is_empty()).then(|| {
+            let layout = Self::layout_for_area(
+                fonts,
+                &spec.title,
+                spec.font_size as f32,
+                right - left,
+                (bottom - top) / 4.0,
+                &spec.text_layout,
+            );
+            PlacedText {
+                layout,
+                center_x: (left + right) / 2.0,
+                top: top + margin,
+            }
+        });
+        let band_top = title.as_ref().map_or(top + margin, |title| {
+            title.top + title.layout.height() + margin * 2.0
+        });
+
+        let (band_left, band_right) = (left + margin, right - margin);
+        let height = ((bottom - margin - band_top).max(1.0)).min((band_right - band_left) / 2.0);
+        let slack = (bottom - margin - band_top - height).max(0.0) / 2.0;
+        let title = title.map(|title| PlacedText {
+            top: title.top + slack,
+            ..title
+        });
+        let band = (
+            band_left,
+            band_top + slack,
+            band_right,
+            band_top + slack + height,
+        );
+        (title, band)
+    }
 
-        // Draw text on image
-        draw_text_mut(&mut image, text_color, x, y, scale, &font, &spec.title);
+    /// Bounding boxes of the title, subtitle, text slides, quote, list, code,
+    /// chart, visualizer, captions and countdown of
+    /// `spec`, for `VideoValidator::check_safe_area`. Image slides are left out:
+    /// they are scaled to fill the frame, so they always reach past the safe area.
+    pub fn layout_report(&self, spec: &VideoSpec) -> Result<LayoutReport> {
+        let fonts = self.load_fonts(spec)?;
 
-        Ok(image)
+        let mut boxes = match &spec.template {
+            super::VideoTemplate::SimpleText => {
+                vec![Self::place_text_block(&fonts, spec, &spec.title).text_box("title")]
+            }
+            super::VideoTemplate::TitleCard => {
+                let card = Self::place_title_card(&fonts, spec);
+                let mut boxes = vec![card.headline.text_box("title")];
+                boxes.extend(card.subtitle.map(|subtitle| subtitle.text_box("subtitle")));
+                boxes
+            }
+            super::VideoTemplate::Slideshow { slides, .. } => slides
+                .iter()
+                .enumerate()
+                .filter(|(_, slide)| !is_image_slide(slide))
+                .map(|(index, slide)| {
+                    Self::place_text_block(&fonts, spec, slide)
+                        .text_box(format!("slide {}", index + 1))
+                })
+                .collect(),
+            super::VideoTemplate::Quote { text, author } => {
+                let card = Self::place_quote(&fonts, spec, text, author.as_deref())?;
+                let mut boxes = vec![card.quote.text_box("quote")];
+                boxes.extend(card.author.map(|author| author.text_box("author")));
+                boxes
+            }
+            super::VideoTemplate::List {
+                heading,
+                items,
+                mode,
+            } => {
+                let (heading, list) = Self::place_list(&fonts, spec, heading, items, *mode)?;
+                let mut boxes = heading
+                    .map(|heading| vec![heading.text_box("heading")])
+                    .unwrap_or_default();
+                boxes.extend(list.text_boxes());
+                boxes
+            }
+            super::VideoTemplate::CodeSnippet { language, code, .. } => {
+                let (title, window) = Self::place_code_snippet(&fonts, spec, language, code)?;
+                let mut boxes = title
+                    .map(|title| vec![title.text_box("title")])
+                    .unwrap_or_default();
+                boxes.push(window.text_box());
+                boxes
+            }
+            super::VideoTemplate::Visualizer { .. } => {
+                let (title, (left, top, right, bottom)) = Self::place_visualizer(&fonts, spec);
+                let mut boxes = title
+                    .map(|title| vec![title.text_box("title")])
+                    .unwrap_or_default();
+                boxes.push(TextBox {
+                    label: "visualizer".to_string(),
+                    left,
+                    top,
+                    right,
+                    bottom,
+                });
+                boxes
+            }
+            super::VideoTemplate::Chart { kind, data } => {
+                let (title, chart) = Self::place_chart(&fonts, spec, *kind, data)?;
+                let mut boxes = title
+                    .map(|title| vec![title.text_box("title")])
+                    .unwrap_or_default();
+                boxes.push(chart.text_box());
+                boxes
+            }
+        };
+
+        if let Some(captions) = &spec.captions {
+            let track = CaptionTrack::load(captions, fonts.clone(), &spec.output_profile)?;
+            boxes.extend(track.text_boxes());
+        }
+        if let Some(countdown) = &spec.countdown {
+            boxes.push(Self::countdown_layer(spec, countdown, fonts).text_box());
+        }
+
+        Ok(LayoutReport {
+            width: spec.output_profile.width,
+            height: spec.output_profile.height,
+            boxes,
+        })
     }
 
-    pub fn generate_video(&self, spec: &VideoSpec) -> Result<PathBuf> {
-        let output_path = self.output_dir.join(format!(
+    /// MP4 path in the output directory named after the sanitized title
+    fn output_path(&self, spec: &VideoSpec) -> PathBuf {
+        self.output_dir.join(format!(
             "{}.mp4",
             spec.title
                 .chars()
                 .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
                 .collect::<String>()
-        ));
+        ))
+    }
 
-        match &spec.template {
-            super::VideoTemplate::SimpleText => {
-                self.generate_simple_text_video(spec, &output_path)?;
+    pub fn generate_video(&self, spec: &VideoSpec) -> Result<PathBuf> {
+        Ok(self.generate_video_with_report(spec)?.output_path)
+    }
+
+    /// Generate a video and report what was measured along the way
+    pub fn generate_video_with_report(&self, spec: &VideoSpec) -> Result<GenerationReport> {
+        spec.output_profile.validate()?;
+
+        let output_path = self.output_path(spec);
+
+        let loudness = match &spec.template {
+            super::VideoTemplate::SimpleText
+            | super::VideoTemplate::TitleCard
+            | super::VideoTemplate::Quote { .. } => {
+                self.generate_title_video(spec, &output_path)?
             }
-            super::VideoTemplate::TitleCard => {
-                todo!("TitleCard template not implemented yet");
+            super::VideoTemplate::Slideshow {
+                slides,
+                durations,
+                transition,
+            } => {
+                self.generate_slideshow_video(spec, &output_path, slides, durations, *transition)?
             }
-            super::VideoTemplate::Slideshow { slides: _ } => {
-                todo!("Slideshow template not implemented yet");
+            super::VideoTemplate::CodeSnippet {
+                language,
+                code,
+                reveal_lines,
+            } => self.generate_code_video(spec, &output_path, language, code, *reveal_lines)?,
+            super::VideoTemplate::List {
+                heading,
+                items,
+                mode,
+            } => self.generate_list_video(spec, &output_path, heading, items, *mode)?,
+            super::VideoTemplate::Chart { kind, data } => {
+                self.generate_chart_video(spec, &output_path, *kind, data)?
             }
-        }
+            super::VideoTemplate::Visualizer { style } => {
+                self.generate_visualizer_video(spec, &output_path, *style)?
+            }
+        };
 
-        Ok(output_path)
+        Ok(GenerationReport {
+            output_path,
+            loudness,
+        })
     }
 
-    fn generate_simple_text_video(&self, spec: &VideoSpec, output_path: &Path) -> Result<()> {
-        // Create text overlay image first
-        let overlay_image = self.create_text_overlay(spec)?;
+    /// Render a custom, possibly animated, template with the spec's output
+    /// profile, duration and audio settings
+    pub fn generate_rendered_video(
+        &self,
+        spec: &VideoSpec,
+        renderer: &mut dyn FrameRenderer,
+    ) -> Result<GenerationReport> {
+        spec.output_profile.validate()?;
+
+        let output_path = self.output_path(spec);
+        let loudness =
+            self.generate_frames_video(spec, &output_path, &mut RendererSource(renderer))?;
+
+        Ok(GenerationReport {
+            output_path,
+            loudness,
+        })
+    }
 
-        // If audio is specified, handle audio-video muxing
-        if let Some(audio_path) = &spec.audio_track {
-            return self.generate_video_with_audio(spec, output_path, &overlay_image, audio_path);
+    /// Encode SimpleText, TitleCard or Quote, still or with the spec's title animation,
+    /// over the background footage when one is set
+    fn generate_title_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+    ) -> Result<Option<LoudnessReport>> {
+        if let Some(animation) = &spec.animation {
+            animation.validate()?;
         }
 
-        // Create a solid color video with text overlay
-        // Target: 1080x1920 (9:16 aspect ratio), 30fps
-
-        let mut output = ffmpeg::format::output(&output_path)?;
-        let global_header = output
-            .format()
-            .flags()
-            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
-
-        // Add video stream
-        let mut video_stream = output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::H264))?;
-        let video_context =
-            ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
-        let mut video_encoder = video_context.encoder().video()?;
-
-        // Configure video encoder
-        video_encoder.set_width(1080);
-        video_encoder.set_height(1920);
-        video_encoder.set_format(ffmpeg::format::Pixel::YUV420P);
-        video_encoder.set_time_base((1, 30)); // 30fps
-        video_encoder.set_frame_rate(Some((30, 1)));
-        video_encoder.set_bit_rate(2_000_000); // 2 Mbps
-        video_encoder.set_max_bit_rate(2_500_000); // 2.5 Mbps max
-        video_encoder.set_gop(30); // GOP size
-        video_encoder.set_qmin(10);
-        video_encoder.set_qmax(51);
-
-        if global_header {
-            video_encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
+        // Laid out once; animations only change how much of it is drawn
+        let fonts = self.load_fonts(spec)?;
+        let title = match &spec.template {
+            super::VideoTemplate::TitleCard => {
+                TitleLayout::Card(Self::place_title_card(&fonts, spec))
+            }
+            super::VideoTemplate::Quote { text, author } => {
+                TitleLayout::Quote(Self::place_quote(&fonts, spec, text, author.as_deref())?)
+            }
+            _ => TitleLayout::Text(Self::place_text_block(&fonts, spec, &spec.title)),
+        };
+        let draw = |image: &mut RgbImage, visible_chars: usize| {
+            title.draw(image, &fonts, spec, visible_chars);
+            Ok(title.char_count())
+        };
+
+        if let Some(Background::Video(video)) = &spec.background {
+            let profile = &spec.output_profile;
+            let footage = Footage::open(video, profile.width, profile.height)?;
+            let mut renderer =
+                FootageRenderer::new(footage, spec.animation, spec.duration_seconds as f32, draw)?;
+            // Decoded frames go straight to the encoder unless an overlay draws on them
+            if !self.draws_overlays(spec) {
+                renderer = renderer.yuv();
+            }
+            return self.generate_frames_video(spec, output_path, &mut renderer);
         }
 
-        let video_encoder =
-            video_encoder.open_as(ffmpeg::encoder::find(ffmpeg::codec::Id::H264))?;
-        video_stream.set_parameters(&video_encoder);
+        let background = self.create_background(spec)?;
+        let mut frame = background.clone();
+        let total_chars = draw(&mut frame, usize::MAX)?;
 
-        // Store time bases before borrowing output mutably
-        let stream_time_base = video_stream.time_base();
-        let encoder_time_base = video_encoder.time_base();
+        let mut renderer: Box<dyn FrameRenderer + '_> = match spec.animation {
+            None => Box::new(StillFrame::new(frame)),
+            Some(TitleAnimation::FadeIn { seconds }) => {
+                Box::new(FadeIn::new(background, frame, seconds))
+            }
+            Some(TitleAnimation::Typewriter {
+                characters_per_second,
+            }) => Box::new(Typewriter::new(
+                characters_per_second,
+                total_chars,
+                move |visible_chars| {
+                    let mut image = background.clone();
+                    draw(&mut image, visible_chars)?;
+                    Ok(image)
+                },
+            )),
+            Some(TitleAnimation::KenBurns { zoom }) => Box::new(KenBurns::new(
+                background,
+                zoom,
+                spec.duration_seconds as f32,
+                move |image: &mut RgbImage| draw(image, usize::MAX).map(|_| ()),
+            )),
+        };
 
-        // Write header
-        output.write_header()?;
+        self.generate_frames_video(spec, output_path, &mut RendererSource(renderer.as_mut()))
+    }
 
-        // Generate frames
-        let total_frames = spec.duration_seconds * 30; // 30fps
-        let mut frame = ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, 1080, 1920);
+    /// Encode a slideshow where each slide is a text or image frame
+    fn generate_slideshow_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        slides: &[String],
+        durations: &[f32],
+        transition: SlideTransition,
+    ) -> Result<Option<LoudnessReport>> {
+        let timeline = SlideTimeline::new(
+            slides.len(),
+            durations,
+            spec.duration_seconds as f32,
+            1.0 / spec.output_profile.frame_rate as f32,
+            transition,
+        )?;
+        let images = slides
+            .iter()
+            .map(|slide| self.create_slide_image(spec, slide))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut current = None;
+        self.generate_frames_video(spec, output_path, &mut |_index: u32, time: f64| {
+            let slide_frame = timeline.frame_at(time as f32);
+            if current == Some(slide_frame) {
+                return Ok(SourceFrame::Repeat);
+            }
+            current = Some(slide_frame);
 
-        // Convert RGB image to YUV frame data
-        self.fill_frame_with_image(&mut frame, &overlay_image)?;
+            let image = match slide_frame {
+                SlideFrame::Single(slide) => images[slide].clone(),
+                SlideFrame::Crossfade { from, to, progress } => {
+                    animation::blend(&images[from], &images[to], progress)
+                }
+            };
+            Ok(SourceFrame::Rgb(image))
+        })
+    }
 
-        // Create a new encoder for the actual encoding process
-        let mut encoder = video_encoder;
+    /// Encode a code snippet in an editor window under the title, scrolling
+    /// long code or revealing it line by line
+    fn generate_code_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        language: &str,
+        code: &str,
+        reveal_lines: bool,
+    ) -> Result<Option<LoudnessReport>> {
+        let fonts = self.load_fonts(spec)?;
+        let (title, window) = Self::place_code_snippet(&fonts, spec, language, code)?;
+
+        let mut background = self.create_background(spec)?;
+        if let Some(title) = title {
+            title.draw(&mut background, &fonts, spec, usize::MAX);
+        }
 
-        for i in 0..total_frames {
-            frame.set_pts(Some(i as i64));
+        let mut renderer = CodeRenderer::new(
+            background,
+            window,
+            reveal_lines,
+            spec.duration_seconds as f64,
+        );
+        self.generate_frames_video(spec, output_path, &mut RendererSource(&mut renderer))
+    }
 
-            encoder.send_frame(&frame)?;
+    /// Encode a numbered list where items appear in turn under a pinned heading
+    fn generate_list_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        heading: &str,
+        items: &[ListItem],
+        mode: ListMode,
+    ) -> Result<Option<LoudnessReport>> {
+        let starts = list::item_starts(items, spec.duration_seconds as f32)?;
+        let fonts = self.load_fonts(spec)?;
+        let (heading, list) = Self::place_list(&fonts, spec, heading, items, mode)?;
+
+        let mut background = self.create_background(spec)?;
+        if let Some(heading) = heading {
+            heading.draw(&mut background, &fonts, spec, usize::MAX);
+        }
 
-            let mut encoded = ffmpeg::packet::Packet::empty();
-            while encoder.receive_packet(&mut encoded).is_ok() {
-                encoded.set_stream(0);
-                encoded.rescale_ts(encoder_time_base, stream_time_base);
-                encoded.write_interleaved(&mut output)?;
+        let colors = (
+            spec.text_color,
+            spec.accent_color.unwrap_or(spec.text_color),
+        );
+        let mut shown_before = None;
+        self.generate_frames_video(spec, output_path, &mut |_index: u32, time: f64| {
+            let shown = starts.iter().filter(|start| **start <= time as f32).count();
+            if shown_before == Some(shown) {
+                return Ok(SourceFrame::Repeat);
             }
+            shown_before = Some(shown);
+
+            let mut image = background.clone();
+            list.draw(&mut image, &fonts, shown, colors, &spec.text_style);
+            Ok(SourceFrame::Rgb(image))
+        })
+    }
+
+    /// Encode a chart growing under the title
+    fn generate_chart_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        kind: ChartKind,
+        data: &ChartData,
+    ) -> Result<Option<LoudnessReport>> {
+        let fonts = self.load_fonts(spec)?;
+        let (title, chart) = Self::place_chart(&fonts, spec, kind, data)?;
+
+        let mut background = self.create_background(spec)?;
+        if let Some(title) = title {
+            title.draw(&mut background, &fonts, spec, usize::MAX);
         }
 
-        // Flush encoder
-        encoder.send_eof()?;
-        let mut encoded = ffmpeg::packet::Packet::empty();
-        while encoder.receive_packet(&mut encoded).is_ok() {
-            encoded.set_stream(0);
-            encoded.rescale_ts(encoder_time_base, stream_time_base);
-            encoded.write_interleaved(&mut output)?;
+        let mut renderer = ChartRenderer::new(background, chart, spec.duration_seconds as f64);
+        self.generate_frames_video(spec, output_path, &mut RendererSource(&mut renderer))
+    }
+
+    /// Encode the title over a spectrum or waveform of the audio track, drawn
+    /// from the same prepared samples that are encoded
+    fn generate_visualizer_video(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        style: VisualizerStyle,
+    ) -> Result<Option<LoudnessReport>> {
+        let audio = self
+            .prepare_audio(spec)?
+            .ok_or_else(|| anyhow::anyhow!("Visualizer template needs an audio_track to draw"))?;
+        let fonts = self.load_fonts(spec)?;
+        let (title, band) = Self::place_visualizer(&fonts, spec);
+
+        let mut background = self.create_background(spec)?;
+        if let Some(title) = title {
+            title.draw(&mut background, &fonts, spec, usize::MAX);
         }
 
-        output.write_trailer()?;
+        let mut renderer = VisualizerRenderer::new(
+            background,
+            style,
+            &audio.buffer,
+            band,
+            spec.accent_color.unwrap_or(spec.text_color),
+        )?;
+        self.generate_frames_video_with_audio(
+            spec,
+            output_path,
+            &mut RendererSource(&mut renderer),
+            Some(&audio),
+        )
+    }
 
-        Ok(())
+    fn countdown_layer(
+        spec: &VideoSpec,
+        countdown: &Countdown,
+        fonts: FontChain,
+    ) -> CountdownLayer {
+        CountdownLayer::new(
+            countdown,
+            fonts,
+            &spec.output_profile,
+            spec.text_color,
+            spec.duration_seconds,
+        )
     }
 
-    /// Generate video with audio track using FFmpeg muxing
-    fn generate_video_with_audio(
+    /// Whether captions, a watermark, a progress bar or a countdown are drawn
+    /// over the frames of the spec
+    fn draws_overlays(&self, spec: &VideoSpec) -> bool {
+        spec.captions.is_some()
+            || spec.watermark.is_some()
+            || self.watermark.is_some()
+            || spec.progress_bar.is_some()
+            || spec.countdown.is_some()
+    }
+
+    /// Encode the frames of `source` for the full duration of the spec,
+    /// together with the audio track and the captions, watermark, progress bar
+    /// and countdown overlays when they are set.
+    ///
+    /// Returns the loudness report when the audio track was normalized.
+    fn generate_frames_video(
         &self,
         spec: &VideoSpec,
         output_path: &Path,
-        overlay_image: &RgbImage,
-        audio_path: &str,
-    ) -> Result<()> {
-        // First, validate that the audio file exists
-        let audio_file_path = Path::new(audio_path);
-        if !audio_file_path.exists() {
-            // Try relative path from assets
-            let assets_audio_path = Path::new("src/assets/audio").join(audio_path);
-            if !assets_audio_path.exists() {
-                return Err(anyhow::anyhow!(
-                    "Audio file not found: {} (also tried {})",
-                    audio_path,
-                    assets_audio_path.display()
-                ));
-            }
+        source: &mut dyn FrameSource,
+    ) -> Result<Option<LoudnessReport>> {
+        let audio = self.prepare_audio(spec)?;
+        self.generate_frames_video_with_audio(spec, output_path, source, audio.as_ref())
+    }
+
+    /// `generate_frames_video` with the audio track already prepared, for
+    /// templates that draw from it
+    fn generate_frames_video_with_audio(
+        &self,
+        spec: &VideoSpec,
+        output_path: &Path,
+        source: &mut dyn FrameSource,
+        audio: Option<&PreparedAudio>,
+    ) -> Result<Option<LoudnessReport>> {
+        // Captions and the countdown share one font chain
+        let fonts = if spec.captions.is_some() || spec.countdown.is_some() {
+            Some(self.load_fonts(spec)?)
+        } else {
+            None
+        };
+
+        let mut overlays: Vec<Box<dyn FrameOverlay>> = Vec::new();
+        if let (Some(captions), Some(fonts)) = (&spec.captions, &fonts) {
+            let track = CaptionTrack::load(captions, fonts.clone(), &spec.output_profile)?;
+            overlays.push(Box::new(track));
+        }
+        if let Some(watermark) = spec.watermark.as_ref().or(self.watermark.as_ref()) {
+            overlays.push(Box::new(WatermarkLayer::new(
+                watermark,
+                &spec.output_profile,
+            )?));
         }
 
-        // Create temporary video without audio first
-        let temp_video_path = self.temp_dir.join(format!(
-            "{}_temp_video.mp4",
-            spec.title
-                .chars()
-                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-                .collect::<String>()
-        ));
+        if let Some(bar) = &spec.progress_bar {
+            overlays.push(Box::new(ProgressBarLayer::new(
+                bar,
+                &spec.output_profile,
+                spec.text_color,
+                spec.duration_seconds,
+            )));
+        }
+        if let (Some(countdown), Some(fonts)) = (&spec.countdown, fonts) {
+            overlays.push(Box::new(Self::countdown_layer(spec, countdown, fonts)));
+        }
 
-        // Generate video-only file
-        self.generate_video_only(spec, &temp_video_path, overlay_image)?;
+        if overlays.is_empty() {
+            return Self::encode_with_audio(spec, output_path, source, audio);
+        }
+        let mut overlaid = OverlaidSource::new(source, overlays);
+        Self::encode_with_audio(spec, output_path, &mut overlaid, audio)
+    }
 
-        // Use FFmpeg to mux video and audio
-        let final_audio_path = if audio_file_path.exists() {
-            audio_file_path.to_path_buf()
-        } else {
-            Path::new("src/assets/audio").join(audio_path)
+    /// Decode the spec's audio track and place, fade and normalize it to the
+    /// length of the video, as it will be encoded
+    fn prepare_audio(&self, spec: &VideoSpec) -> Result<Option<PreparedAudio>> {
+        let Some(audio_path) = &spec.audio_track else {
+            return Ok(None);
         };
 
-        self.mux_video_with_audio(&temp_video_path, &final_audio_path, output_path, spec.duration_seconds)?;
+        let audio_file_path = audio::resolve_audio_path(audio_path)?;
 
-        // Clean up temporary file
-        std::fs::remove_file(&temp_video_path)?;
+        // Decode from the start offset and only as much as the video needs;
+        // the video length always wins
+        let options = &spec.audio_options;
+        let samples = spec.duration_seconds as usize * audio::SAMPLE_RATE as usize;
+        let mut buffer = audio::decode_audio(
+            &audio_file_path,
+            options.start_offset_seconds,
+            Some(samples),
+        )?;
+        buffer.apply_options(options, samples)?;
 
-        Ok(())
+        let loudness = options.normalize.map(|target| buffer.normalize(&target));
+
+        Ok(Some(PreparedAudio { buffer, loudness }))
     }
 
-    /// Generate video-only content (for later audio muxing)
-    fn generate_video_only(
-        &self,
+    /// Run the encode pipeline with the prepared audio track, if there is one
+    fn encode_with_audio(
         spec: &VideoSpec,
         output_path: &Path,
-        overlay_image: &RgbImage,
-    ) -> Result<()> {
-        let mut output = ffmpeg::format::output(&output_path)?;
-        let global_header = output
-            .format()
-            .flags()
-            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
-
-        // Add video stream
-        let mut video_stream = output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::H264))?;
-        let video_context =
-            ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
-        let mut video_encoder = video_context.encoder().video()?;
-
-        // Configure video encoder
-        video_encoder.set_width(1080);
-        video_encoder.set_height(1920);
-        video_encoder.set_format(ffmpeg::format::Pixel::YUV420P);
-        video_encoder.set_time_base((1, 30)); // 30fps
-        video_encoder.set_frame_rate(Some((30, 1)));
-        video_encoder.set_bit_rate(2_000_000); // 2 Mbps
-        video_encoder.set_max_bit_rate(2_500_000); // 2.5 Mbps max
-        video_encoder.set_gop(30); // GOP size
-        video_encoder.set_qmin(10);
-        video_encoder.set_qmax(51);
-
-        if global_header {
-            video_encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
-        }
-
-        let video_encoder =
-            video_encoder.open_as(ffmpeg::encoder::find(ffmpeg::codec::Id::H264))?;
-        video_stream.set_parameters(&video_encoder);
+        source: &mut dyn FrameSource,
+        audio: Option<&PreparedAudio>,
+    ) -> Result<Option<LoudnessReport>> {
+        let pipeline = EncodePipeline::new(&spec.output_profile, spec.duration_seconds);
+
+        let Some(audio) = audio else {
+            pipeline.encode(output_path, source)?;
+            return Ok(None);
+        };
 
-        // Store time bases before borrowing output mutably
-        let stream_time_base = video_stream.time_base();
-        let encoder_time_base = video_encoder.time_base();
+        pipeline
+            .with_audio(&audio.buffer)
+            .encode(output_path, source)?;
+        Ok(audio.loudness)
+    }
+}
 
-        // Write header
-        output.write_header()?;
+/// Audio track ready to encode, with its normalization report
+struct PreparedAudio {
+    buffer: audio::AudioBuffer,
+    loudness: Option<LoudnessReport>,
+}
 
-        // Generate frames
-        let total_frames = spec.duration_seconds * 30; // 30fps
-        let mut frame = ffmpeg::frame::Video::new(ffmpeg::format::Pixel::YUV420P, 1080, 1920);
+/// Slides ending in an image extension are loaded from disk
+fn is_image_slide(slide: &str) -> bool {
+    Path::new(slide)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "png" | "jpg" | "jpeg" | "webp" | "bmp"
+            )
+        })
+        .unwrap_or(false)
+}
 
-        // Convert RGB image to YUV frame data
-        self.fill_frame_with_image(&mut frame, overlay_image)?;
+/// Text laid out and positioned in the frame
+struct PlacedText {
+    layout: TextLayout,
+    center_x: f32,
+    top: f32,
+}
 
-        // Create a new encoder for the actual encoding process
-        let mut encoder = video_encoder;
+impl PlacedText {
+    fn draw(
+        &self,
+        image: &mut RgbImage,
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        visible_chars: usize,
+    ) {
+        text::draw_styled_layout_prefix(
+            image,
+            fonts,
+            &self.layout,
+            spec.text_color,
+            &spec.text_style,
+            self.center_x,
+            self.top,
+            visible_chars,
+        );
+    }
 
-        for i in 0..total_frames {
-            frame.set_pts(Some(i as i64));
+    fn text_box(&self, label: impl Into<String>) -> TextBox {
+        TextBox::around(label, &self.layout, self.center_x, self.top)
+    }
+}
 
-            encoder.send_frame(&frame)?;
+/// Headline, accent bar as (x, y, width, height) and subtitle of a title card
+struct TitleCardLayout {
+    headline: PlacedText,
+    bar: (u32, u32, u32, u32),
+    subtitle: Option<PlacedText>,
+}
 
-            let mut encoded = ffmpeg::packet::Packet::empty();
-            while encoder.receive_packet(&mut encoded).is_ok() {
-                encoded.set_stream(0);
-                encoded.rescale_ts(encoder_time_base, stream_time_base);
-                encoded.write_interleaved(&mut output)?;
+impl TitleCardLayout {
+    /// Draw the card with the first `visible_chars` characters of the headline
+    fn draw(
+        &self,
+        image: &mut RgbImage,
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        visible_chars: usize,
+    ) {
+        let (width, height) = image.dimensions();
+        self.headline.draw(image, fonts, spec, visible_chars);
+
+        // Accent falls back to the text color when not specified. Blended rather
+        // than filled so a translucent accent lets the background through
+        let accent_color = spec.accent_color.unwrap_or(spec.text_color);
+        let (bar_x, bar_y, bar_width, bar_height) = self.bar;
+        for py in bar_y..(bar_y + bar_height).min(height) {
+            for px in bar_x..(bar_x + bar_width).min(width) {
+                accent_color.blend(image.get_pixel_mut(px, py), 1.0);
             }
         }
 
-        // Flush encoder
-        encoder.send_eof()?;
-        let mut encoded = ffmpeg::packet::Packet::empty();
-        while encoder.receive_packet(&mut encoded).is_ok() {
-            encoded.set_stream(0);
-            encoded.rescale_ts(encoder_time_base, stream_time_base);
-            encoded.write_interleaved(&mut output)?;
+        if let Some(subtitle) = &self.subtitle {
+            subtitle.draw(image, fonts, spec, usize::MAX);
         }
-
-        output.write_trailer()?;
-
-        Ok(())
     }
+}
 
-    /// Mux video and audio using FFmpeg
-    fn mux_video_with_audio(
+/// Quote marks as (pen x, baseline), quote text and attribution of a quote card
+struct QuoteLayout {
+    mark_size: f32,
+    open_mark: (f32, f32),
+    close_mark: (f32, f32),
+    quote: PlacedText,
+    author: Option<PlacedText>,
+}
+
+impl QuoteLayout {
+    /// Draw the card with the first `visible_chars` characters of the quote
+    fn draw(
         &self,
-        video_path: &Path,
-        audio_path: &Path,
-        output_path: &Path,
-        target_duration: u32,
-    ) -> Result<()> {
-        // Use FFmpeg command-line for reliable audio-video muxing
-        let mut cmd = std::process::Command::new("ffmpeg");
-        cmd.arg("-y") // Overwrite output file
-            .arg("-i").arg(video_path) // Input video
-            .arg("-i").arg(audio_path) // Input audio
-            .arg("-c:v").arg("copy") // Copy video stream (no re-encoding)
-            .arg("-c:a").arg("aac") // Encode audio as AAC
-            .arg("-b:a").arg("128k") // Audio bitrate
-            .arg("-ar").arg("44100") // Audio sample rate
-            .arg("-ac").arg("2") // Stereo audio
-            .arg("-shortest") // End when shortest stream ends
-            .arg("-t").arg(target_duration.to_string()) // Limit to target duration
-            .arg(output_path);
-
-        let output = cmd.output()?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!(
-                "FFmpeg muxing failed: {}",
-                stderr
-            ));
+        image: &mut RgbImage,
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        visible_chars: usize,
+    ) {
+        let accent_color = spec.accent_color.unwrap_or(spec.text_color);
+        for (mark, (x, baseline)) in [("\u{201C}", self.open_mark), ("\u{201D}", self.close_mark)] {
+            text::draw_text_run(
+                image,
+                fonts,
+                mark,
+                self.mark_size,
+                x,
+                baseline,
+                accent_color,
+            );
         }
 
-        Ok(())
+        self.quote.draw(image, fonts, spec, visible_chars);
+        if let Some(author) = &self.author {
+            author.draw(image, fonts, spec, usize::MAX);
+        }
     }
+}
 
-    /// Fill FFmpeg frame with RGB image data converted to YUV
-    fn fill_frame_with_image(
+/// Title of the SimpleText, TitleCard or Quote template, placed in the frame
+enum TitleLayout {
+    Text(PlacedText),
+    Card(TitleCardLayout),
+    Quote(QuoteLayout),
+}
+
+impl TitleLayout {
+    /// Draw the title with only the first `visible_chars` characters of its
+    /// animated text
+    fn draw(
         &self,
-        frame: &mut ffmpeg::frame::Video,
-        image: &RgbImage,
-    ) -> Result<()> {
-        let width = image.width() as usize;
-        let height = image.height() as usize;
-
-        unsafe {
-            let y_size = width * height;
-            let uv_size = (width / 2) * (height / 2);
-
-            let y_plane = std::slice::from_raw_parts_mut(frame.data_mut(0).as_mut_ptr(), y_size);
-            let u_plane = std::slice::from_raw_parts_mut(frame.data_mut(1).as_mut_ptr(), uv_size);
-            let v_plane = std::slice::from_raw_parts_mut(frame.data_mut(2).as_mut_ptr(), uv_size);
-
-            // Convert RGB to YUV420P
-            for y in 0..height {
-                for x in 0..width {
-                    let pixel = image.get_pixel(x as u32, y as u32);
-                    let (yuv_y, yuv_u, yuv_v) = Self::rgb_to_yuv(pixel[0], pixel[1], pixel[2]);
-
-                    // Y plane (full resolution)
-                    y_plane[y * width + x] = yuv_y;
-
-                    // U and V planes (half resolution - 4:2:0 subsampling)
-                    if x % 2 == 0 && y % 2 == 0 {
-                        let uv_idx = (y / 2) * (width / 2) + (x / 2);
-                        u_plane[uv_idx] = yuv_u;
-                        v_plane[uv_idx] = yuv_v;
-                    }
-                }
-            }
+        image: &mut RgbImage,
+        fonts: &FontChain,
+        spec: &VideoSpec,
+        visible_chars: usize,
+    ) {
+        match self {
+            TitleLayout::Text(block) => block.draw(image, fonts, spec, visible_chars),
+            TitleLayout::Card(card) => card.draw(image, fonts, spec, visible_chars),
+            TitleLayout::Quote(card) => card.draw(image, fonts, spec, visible_chars),
         }
+    }
 
-        Ok(())
+    /// Characters of the animated text: the block, the headline or the quote
+    fn char_count(&self) -> usize {
+        match self {
+            TitleLayout::Text(block) => block.layout.char_count(),
+            TitleLayout::Card(card) => card.headline.layout.char_count(),
+            TitleLayout::Quote(card) => card.quote.layout.char_count(),
+        }
     }
 }
 
@@ -442,10 +1228,11 @@ mod tests {
             title: "Test Video".to_string(),
             template: crate::video::VideoTemplate::SimpleText,
             duration_seconds: 5,
-            background_color: "#1a1a1a".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#1a1a1a".parse().unwrap(),
+            text_color: "#ffffff".parse().unwrap(),
             font_size: 48,
             audio_track: None,
+            ..Default::default()
         };
 
         let result = generator.generate_video(&spec);
@@ -455,4 +1242,166 @@ mod tests {
         assert!(output_path.exists());
         assert!(output_path.extension().unwrap() == "mp4");
     }
+
+    #[test]
+    fn test_title_card_video_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let spec = VideoSpec {
+            title: "Title Card Test".to_string(),
+            template: crate::video::VideoTemplate::TitleCard,
+            duration_seconds: 5,
+            background_color: "#ff6b6b".parse().unwrap(),
+            text_color: "#ffffff".parse().unwrap(),
+            font_size: 48,
+            audio_track: None,
+            subtitle: Some("Quick Python Trick".to_string()),
+            accent_color: Some("#2c3e50".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let output_path = generator.generate_video(&spec).unwrap();
+        assert!(output_path.exists());
+    }
+
+    #[test]
+    fn test_animated_title_card_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let spec = VideoSpec {
+            title: "Typewriter Test".to_string(),
+            template: crate::video::VideoTemplate::TitleCard,
+            duration_seconds: 5,
+            subtitle: Some("One letter at a time".to_string()),
+            animation: Some(TitleAnimation::Typewriter {
+                characters_per_second: 8.0,
+            }),
+            ..Default::default()
+        };
+
+        let output_path = generator.generate_video(&spec).unwrap();
+        assert!(output_path.exists());
+    }
+
+    #[test]
+    fn test_slideshow_video_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let spec = VideoSpec {
+            title: "Slideshow Test".to_string(),
+            template: crate::video::VideoTemplate::Slideshow {
+                slides: vec!["First".to_string(), "Second".to_string()],
+                durations: vec![],
+                transition: crate::video::SlideTransition::Crossfade { seconds: 0.5 },
+            },
+            duration_seconds: 4,
+            background_color: "#4ecdc4".parse().unwrap(),
+            text_color: "#2c3e50".parse().unwrap(),
+            font_size: 48,
+            audio_track: None,
+            ..Default::default()
+        };
+
+        let output_path = generator.generate_video(&spec).unwrap();
+        assert!(output_path.exists());
+    }
+
+    #[test]
+    fn test_audio_video_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        // 10 second tone into a 6 second video: audio is cut, video length is kept
+        let spec = VideoSpec {
+            title: "Audio Test".to_string(),
+            template: crate::video::VideoTemplate::SimpleText,
+            duration_seconds: 6,
+            background_color: "#1a1a1a".parse().unwrap(),
+            text_color: "#ffffff".parse().unwrap(),
+            font_size: 48,
+            audio_track: Some("test_tone_10s.wav".to_string()),
+            ..Default::default()
+        };
+
+        let output_path = generator.generate_video(&spec).unwrap();
+        let validation = crate::video::VideoValidator::validate_video(&output_path).unwrap();
+        assert!(validation.has_audio);
+
+        let duration = crate::video::VideoValidator::probe_duration(&output_path).unwrap();
+        assert!((duration - 6.0).abs() <= 1.0 / 30.0);
+    }
+
+    #[test]
+    fn test_quote_layout_fits_or_explains() {
+        let spec = VideoSpec {
+            font_size: 56,
+            ..Default::default()
+        };
+        let fonts = FontChain::default();
+
+        let card = VideoGenerator::place_quote(
+            &fonts,
+            &spec,
+            "Simplicity is prerequisite for reliability.",
+            Some("Edsger W. Dijkstra"),
+        )
+        .unwrap();
+        let quote = card.quote.text_box("quote");
+        let author = card.author.unwrap().text_box("author");
+        assert!((author.right - quote.right).abs() < 0.5); // right-aligned
+        assert!(author.top > quote.bottom);
+
+        // Marks sit above and below the quote, flush with its edges
+        let open = text::ink_bounds(&fonts, card.mark_size, "\u{201C}");
+        let close = text::ink_bounds(&fonts, card.mark_size, "\u{201D}");
+        assert!(card.open_mark.1 + open.3 < quote.top);
+        assert!(card.close_mark.1 + close.1 > quote.bottom);
+        assert!((card.open_mark.0 + open.0 - quote.left).abs() < 0.5);
+        assert!((card.close_mark.0 + close.2 - quote.right).abs() < 0.5);
+
+        let rambling = "All work and no play makes a dull video. ".repeat(80);
+        let Err(error) = VideoGenerator::place_quote(&fonts, &spec, &rambling, None) else {
+            panic!("a quote this long can't fit");
+        };
+        assert!(error.to_string().contains("doesn't fit"));
+    }
+
+    #[test]
+    fn test_quote_author_stays_in_safe_area() {
+        let spec = VideoSpec {
+            font_size: 56,
+            ..Default::default()
+        };
+        let fonts = FontChain::default();
+        let (left, _, right, _) = VideoGenerator::text_area(&spec);
+
+        // The attribution is much wider than the quote it is aligned to
+        let card = VideoGenerator::place_quote(
+            &fonts,
+            &spec,
+            "Less.",
+            Some("Ludwig Mies van der Rohe, architect and director of the Bauhaus"),
+        )
+        .unwrap();
+        let quote = card.quote.text_box("quote");
+        let author = card.author.unwrap().text_box("author");
+        assert!(author.right - author.left > quote.right - quote.left);
+        assert!(author.left >= left - 0.5, "{} < {}", author.left, left);
+        assert!(author.right <= right + 0.5, "{} > {}", author.right, right);
+    }
 }
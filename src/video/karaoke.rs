@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+use super::captions::Cue;
+use super::color::Color;
+use super::text::TextLayout;
+
+/// Word-level timings of the speech in a video, the input of karaoke captions.
+///
+/// Read from JSON of this shape, with times in seconds from the start of the video:
+///
+/// ```json
+/// {
+///   "words": [
+///     { "text": "Most", "start": 0.00, "end": 0.31 },
+///     { "text": "viewers", "start": 0.31, "end": 0.78 },
+///     { "text": "watch", "start": 0.92, "end": 1.20 }
+///   ]
+/// }
+/// ```
+///
+/// Speech-to-text tools report words in slightly different shapes (Whisper's
+/// `word`, millisecond offsets, nested segments); converting them means
+/// flattening to this list in spoken order. Punctuation stays attached to the word.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub words: Vec<TranscriptWord>,
+}
+
+/// One spoken word and when it is heard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub text: String,
+    pub start: f64, // seconds
+    pub end: f64,
+}
+
+impl Transcript {
+    /// Read a JSON transcript and check that the words are timed in order
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+        let transcript: Self = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse transcript {}", path.display()))?;
+        transcript.validate()?;
+        Ok(transcript)
+    }
+
+    /// Words must have text and finite times, end after they start and not
+    /// start before the previous word
+    pub fn validate(&self) -> Result<()> {
+        let mut previous_start = f64::MIN;
+        for (index, word) in self.words.iter().enumerate() {
+            if word.text.trim().is_empty() {
+                return Err(anyhow::anyhow!("Transcript word {} has no text", index));
+            }
+            if !word.start.is_finite() || !word.end.is_finite() {
+                return Err(anyhow::anyhow!(
+                    "Transcript word {} ('{}') has a time that is not a finite number",
+                    index,
+                    word.text
+                ));
+            }
+            if word.start < previous_start || word.end <= word.start {
+                return Err(anyhow::anyhow!(
+                    "Transcript word {} ('{}') is timed {}s - {}s, out of order",
+                    index,
+                    word.text,
+                    word.start,
+                    word.end
+                ));
+            }
+            previous_start = word.start;
+        }
+        Ok(())
+    }
+
+    /// Split the words into caption-sized phrases: a phrase ends after
+    /// `max_words` or before a pause longer than `max_gap_seconds`
+    pub(crate) fn phrases(&self, style: &KaraokeStyle) -> Vec<Phrase> {
+        let mut phrases = Vec::new();
+        let mut words: Vec<&TranscriptWord> = Vec::new();
+
+        for word in &self.words {
+            let pause = words
+                .last()
+                .is_some_and(|last| word.start - last.end > style.max_gap_seconds);
+            if !words.is_empty() && (words.len() >= style.max_words.max(1) || pause) {
+                phrases.push(Phrase::new(&words));
+                words.clear();
+            }
+            words.push(word);
+        }
+        if !words.is_empty() {
+            phrases.push(Phrase::new(&words));
+        }
+
+        phrases
+    }
+}
+
+/// Words shown together as one caption
+pub(crate) struct Phrase {
+    pub(crate) cue: Cue,
+    pub(crate) words: Vec<(f64, Range<usize>)>, // start time and characters in `cue.text`
+}
+
+impl Phrase {
+    fn new(words: &[&TranscriptWord]) -> Self {
+        let mut text = String::new();
+        let mut timed = Vec::new();
+        for word in words {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let start = text.chars().count();
+            text.push_str(word.text.trim());
+            timed.push((word.start, start..text.chars().count()));
+        }
+
+        Self {
+            cue: Cue {
+                start: words[0].start,
+                end: words[words.len() - 1].end,
+                text,
+            },
+            words: timed,
+        }
+    }
+}
+
+/// How the word being spoken stands out from the rest of its phrase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KaraokeStyle {
+    pub highlight_color: Color,
+    pub highlight_scale: f32, // 1.0 keeps the word at the caption size
+    pub dim_opacity: f32,     // 0.0 - 1.0 for the words not being spoken
+    pub max_words: usize,     // per phrase
+    pub max_gap_seconds: f64, // a longer pause starts a new phrase
+}
+
+impl Default for KaraokeStyle {
+    fn default() -> Self {
+        Self {
+            highlight_color: Color::rgb(255, 214, 0),
+            highlight_scale: 1.15,
+            dim_opacity: 0.6,
+            max_words: 5,
+            max_gap_seconds: 0.8,
+        }
+    }
+}
+
+/// Map character ranges of the phrase text to the characters of its wrapped
+/// layout, which drops the spaces where lines break
+pub(crate) fn layout_ranges(
+    layout: &TextLayout,
+    text: &str,
+    ranges: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    // Every non-space character keeps its order, so pair them up one by one
+    let text_positions = text
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(index, _)| index);
+    let layout_positions = layout
+        .lines
+        .iter()
+        .flat_map(|line| line.text.chars())
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(index, _)| index);
+    let pairs = text_positions.zip(layout_positions).collect::<Vec<_>>();
+
+    ranges
+        .iter()
+        .map(|range| {
+            let mut inside = pairs
+                .iter()
+                .filter(|(text_index, _)| range.contains(text_index))
+                .map(|(_, layout_index)| *layout_index);
+            match inside.next() {
+                Some(first) => first..inside.next_back().unwrap_or(first) + 1,
+                None => 0..0,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::text::{layout_text, TextLayoutOptions};
+    use crate::video::FontChain;
+
+    fn word(text: &str, start: f64, end: f64) -> TranscriptWord {
+        TranscriptWord {
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_transcript_splits_into_phrases() {
+        let json = r#"{"words": [
+            {"text": "Most", "start": 0.0, "end": 0.3},
+            {"text": "viewers", "start": 0.3, "end": 0.7},
+            {"text": "watch", "start": 0.7, "end": 1.0},
+            {"text": "muted.", "start": 2.5, "end": 3.0}
+        ]}"#;
+        let transcript: Transcript = serde_json::from_str(json).unwrap();
+        transcript.validate().unwrap();
+
+        let phrases = transcript.phrases(&KaraokeStyle::default());
+        assert_eq!(phrases.len(), 2); // split at the pause
+        assert_eq!(phrases[0].cue.text, "Most viewers watch");
+        assert_eq!((phrases[0].cue.start, phrases[0].cue.end), (0.0, 1.0));
+        assert_eq!(phrases[0].words[1], (0.3, 5..12));
+
+        let short = KaraokeStyle {
+            max_words: 2,
+            ..Default::default()
+        };
+        assert_eq!(transcript.phrases(&short).len(), 3);
+
+        let backwards = Transcript {
+            words: vec![word("late", 2.0, 2.5), word("early", 1.0, 1.5)],
+        };
+        assert!(backwards.validate().is_err());
+
+        let untimed = Transcript {
+            words: vec![word("now", 0.0, 0.5), word("never", f64::NAN, 1.0)],
+        };
+        assert!(untimed.validate().is_err());
+    }
+
+    #[test]
+    fn test_word_ranges_survive_wrapping() {
+        let phrase = Phrase::new(&[
+            &word("Wrapped", 0.0, 0.5),
+            &word("caption", 0.5, 1.0),
+            &word("words", 1.0, 1.5),
+        ]);
+        let options = TextLayoutOptions::default();
+        let layout = layout_text(
+            &FontChain::default(),
+            &phrase.cue.text,
+            40.0,
+            200.0,
+            1000.0,
+            &options,
+        );
+        assert!(layout.lines.len() > 1);
+
+        let ranges = phrase
+            .words
+            .iter()
+            .map(|(_, range)| range.clone())
+            .collect::<Vec<_>>();
+        let mapped = layout_ranges(&layout, &phrase.cue.text, &ranges);
+        let glyphs = layout
+            .lines
+            .iter()
+            .flat_map(|line| line.text.chars())
+            .collect::<Vec<_>>();
+        for (range, expected) in mapped.into_iter().zip(["Wrapped", "caption", "words"]) {
+            assert_eq!(glyphs[range].iter().collect::<String>(), expected);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use super::text::TextLayout;
+
+/// Where the text of a video ends up in the frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutReport {
+    pub width: u32, // frame size the boxes refer to
+    pub height: u32,
+    pub boxes: Vec<TextBox>,
+}
+
+/// Bounding box of one piece of rendered text, in px from the top-left corner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextBox {
+    pub label: String, // e.g. "title", "subtitle", "caption 3"
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl TextBox {
+    /// Ink extent of `layout` drawn centered on `center_x` with its top at `top_y`
+    pub(crate) fn around(
+        label: impl Into<String>,
+        layout: &TextLayout,
+        center_x: f32,
+        top_y: f32,
+    ) -> Self {
+        let half_width = layout.width() / 2.0;
+        Self {
+            label: label.into(),
+            left: center_x - half_width,
+            top: top_y,
+            right: center_x + half_width,
+            bottom: top_y + layout.height(),
+        }
+    }
+}
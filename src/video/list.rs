@@ -0,0 +1,369 @@
+use anyhow::Result;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use super::color::Color;
+use super::fonts::FontChain;
+use super::layout::TextBox;
+use super::text::{self, TextLayout, TextLayoutOptions, TextStyle};
+
+/// One entry of a List template. Written as a plain string it gets an equal
+/// share of the time left over by the items with their own `seconds`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ListItemRepr")]
+pub struct ListItem {
+    pub text: String,
+    pub seconds: Option<f32>, // how long before the next item appears
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListItemRepr {
+    Text(String),
+    Timed {
+        text: String,
+        #[serde(default)]
+        seconds: Option<f32>,
+    },
+}
+
+impl From<ListItemRepr> for ListItem {
+    fn from(repr: ListItemRepr) -> Self {
+        match repr {
+            ListItemRepr::Text(text) => Self {
+                text,
+                seconds: None,
+            },
+            ListItemRepr::Timed { text, seconds } => Self { text, seconds },
+        }
+    }
+}
+
+impl From<&str> for ListItem {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            seconds: None,
+        }
+    }
+}
+
+/// Whether earlier items stay on screen when the next one appears
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListMode {
+    #[default]
+    Accumulate,
+    Replace,
+}
+
+/// Second at which each item appears
+pub(crate) fn item_starts(items: &[ListItem], total_seconds: f32) -> Result<Vec<f32>> {
+    if items.is_empty() {
+        return Err(anyhow::anyhow!("List template needs at least one item"));
+    }
+    if items
+        .iter()
+        .filter_map(|item| item.seconds)
+        .any(|seconds| seconds.is_nan() || seconds <= 0.0)
+    {
+        return Err(anyhow::anyhow!("List item seconds must be positive"));
+    }
+
+    let timed: f32 = items.iter().filter_map(|item| item.seconds).sum();
+    let untimed = items.iter().filter(|item| item.seconds.is_none()).count();
+    // A millisecond of slack for rounding in the sum
+    if timed > total_seconds + 0.001 {
+        return Err(anyhow::anyhow!(
+            "List items with their own timing take {}s of a {}s video, \
+             so the last ones would never appear",
+            timed,
+            total_seconds
+        ));
+    }
+    let share = if untimed > 0 {
+        let left = total_seconds - timed;
+        if left <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "List items with their own timing take {}s of a {}s video, \
+                 leaving none for the other {}",
+                timed,
+                total_seconds,
+                untimed
+            ));
+        }
+        left / untimed as f32
+    } else {
+        0.0
+    };
+
+    let mut elapsed = 0.0;
+    Ok(items
+        .iter()
+        .map(|item| {
+            let start = elapsed;
+            elapsed += item.seconds.unwrap_or(share);
+            start
+        })
+        .collect())
+}
+
+/// An item wrapped and placed in the frame, with its number to the left
+struct ListEntry {
+    number: TextLayout,
+    number_right: f32, // right edge of the number
+    layout: TextLayout,
+    left: f32, // left edge of the text
+    top: f32,
+}
+
+/// Where every item of a list is drawn; in Replace mode each item has the
+/// whole area to itself
+pub(crate) struct ListLayout {
+    entries: Vec<ListEntry>,
+    mode: ListMode,
+}
+
+impl ListLayout {
+    /// Wrap the items to `area` (left, top, right, bottom), starting at
+    /// `font_size` and shrinking down to `options.min_font_size` until they fit.
+    /// Replace mode starts a quarter larger, as one item is shown at a time.
+    pub(crate) fn new(
+        fonts: &FontChain,
+        items: &[ListItem],
+        mode: ListMode,
+        area: (f32, f32, f32, f32),
+        font_size: f32,
+        options: &TextLayoutOptions,
+    ) -> Result<Self> {
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("List template needs at least one item"));
+        }
+        let options = TextLayoutOptions {
+            margin: 0,
+            shrink_to_fit: false,
+            ..options.clone()
+        };
+        let min_font_size = (options.min_font_size as f32).max(1.0);
+        let (left, top, right, bottom) = area;
+        let height = bottom - top;
+
+        let groups = match mode {
+            ListMode::Accumulate => vec![(0..items.len(), font_size)],
+            ListMode::Replace => (0..items.len())
+                .map(|index| (index..index + 1, font_size * 1.25))
+                .collect(),
+        };
+
+        let mut entries = Vec::new();
+        for (range, start_size) in groups {
+            let numbers = range.clone().map(|index| format!("{}.", index + 1));
+            let texts = &items[range.clone()];
+
+            let mut size = start_size;
+            let (number_width, layouts, total) = loop {
+                let number_width = numbers
+                    .clone()
+                    .map(|number| text::measure_text(fonts, size, &number))
+                    .fold(0.0, f32::max)
+                    + size * 0.4;
+                let text_width = (right - left - number_width).max(1.0);
+                let layouts = texts
+                    .iter()
+                    .map(|item| {
+                        text::layout_text(fonts, &item.text, size, text_width, f32::MAX, &options)
+                    })
+                    .collect::<Vec<_>>();
+                let total = layouts.iter().map(TextLayout::height).sum::<f32>()
+                    + size * 0.6 * (layouts.len() - 1) as f32;
+
+                if total <= height || size <= min_font_size {
+                    break (number_width, layouts, total);
+                }
+                size = (size * 0.9).max(min_font_size);
+            };
+            if total > height {
+                return Err(anyhow::anyhow!(
+                    "List items don't fit below the heading: at {:.0}px they need {:.0}px \
+                     but {:.0}px are available; shorten the items or lower text_layout.min_font_size",
+                    size,
+                    total,
+                    height
+                ));
+            }
+
+            // A left-aligned column, centered as a whole
+            let block_width =
+                number_width + layouts.iter().map(TextLayout::width).fold(0.0, f32::max);
+            let block_left = (left + right - block_width) / 2.0;
+            let mut y = top + (height - total) / 2.0;
+            for (number, layout) in numbers.zip(layouts) {
+                let number = text::layout_text(fonts, &number, size, f32::MAX, f32::MAX, &options);
+                let entry_height = layout.height();
+                entries.push(ListEntry {
+                    number,
+                    number_right: block_left + number_width - size * 0.4,
+                    left: block_left + number_width,
+                    top: y,
+                    layout,
+                });
+                y += entry_height + size * 0.6;
+            }
+        }
+
+        Ok(Self { entries, mode })
+    }
+
+    /// Where every item is drawn, for safe-area checks
+    pub(crate) fn text_boxes(&self) -> Vec<TextBox> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| TextBox {
+                label: format!("item {}", index + 1),
+                left: entry.number_right - entry.number.width(),
+                top: entry.top,
+                right: entry.left + entry.layout.width(),
+                bottom: entry.top + entry.layout.height(),
+            })
+            .collect()
+    }
+
+    /// Draw the list as it looks once `shown` items have appeared
+    pub(crate) fn draw(
+        &self,
+        image: &mut RgbImage,
+        fonts: &FontChain,
+        shown: usize,
+        colors: (Color, Color), // text and number
+        style: &TextStyle,
+    ) {
+        let visible = match self.mode {
+            ListMode::Accumulate => 0..shown.min(self.entries.len()),
+            ListMode::Replace => {
+                shown.clamp(1, self.entries.len()) - 1..shown.min(self.entries.len())
+            }
+        };
+        for entry in &self.entries[visible] {
+            let number_left = entry.number_right - entry.number.width();
+            draw_left_aligned(
+                image,
+                fonts,
+                &entry.number,
+                number_left,
+                entry.top,
+                colors.1,
+                style,
+            );
+            draw_left_aligned(
+                image,
+                fonts,
+                &entry.layout,
+                entry.left,
+                entry.top,
+                colors.0,
+                style,
+            );
+        }
+    }
+}
+
+/// Draw every line of `layout` with its ink starting at `left`
+fn draw_left_aligned(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    layout: &TextLayout,
+    left: f32,
+    top: f32,
+    color: Color,
+    style: &TextStyle,
+) {
+    for (index, line) in layout.lines.iter().enumerate() {
+        let single = TextLayout {
+            lines: vec![line.clone()],
+            ..layout.clone()
+        };
+        text::draw_styled_layout_prefix(
+            image,
+            fonts,
+            &single,
+            color,
+            style,
+            left + line.ink_width() / 2.0,
+            top + index as f32 * layout.line_height,
+            usize::MAX,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_timing() {
+        let items: Vec<ListItem> = serde_json::from_str(
+            r#"["Plan", {"text": "Record", "seconds": 6}, "Edit", {"text": "Post"}]"#,
+        )
+        .unwrap();
+        assert_eq!(items[1].seconds, Some(6.0));
+
+        // 6s for "Record", the other three share the remaining 9s
+        assert_eq!(
+            item_starts(&items, 15.0).unwrap(),
+            vec![0.0, 3.0, 9.0, 12.0]
+        );
+        assert!(item_starts(&items, 6.0).is_err());
+        assert!(item_starts(&[], 15.0).is_err());
+
+        // Every item timed: they may end early, but not run past the video
+        let timed = |seconds: [f32; 3]| {
+            seconds
+                .map(|seconds| ListItem {
+                    text: "Step".to_string(),
+                    seconds: Some(seconds),
+                })
+                .to_vec()
+        };
+        assert_eq!(
+            item_starts(&timed([2.0, 3.0, 4.0]), 10.0).unwrap(),
+            vec![0.0, 2.0, 5.0]
+        );
+        let Err(error) = item_starts(&timed([4.0, 4.0, 4.0]), 10.0) else {
+            panic!("items longer than the video");
+        };
+        assert!(error.to_string().contains("12s"), "{}", error);
+    }
+
+    #[test]
+    fn test_accumulate_stacks_and_replace_centers() {
+        let fonts = FontChain::default();
+        let items = ["Short", "A much longer item that wraps", "Third"]
+            .map(ListItem::from)
+            .to_vec();
+        let area = (0.0, 100.0, 600.0, 900.0);
+        let options = TextLayoutOptions::default();
+
+        let list =
+            ListLayout::new(&fonts, &items, ListMode::Accumulate, area, 48.0, &options).unwrap();
+        let boxes = list.text_boxes();
+        assert!(boxes[0].bottom < boxes[1].top && boxes[1].bottom < boxes[2].top);
+        // Texts share a left edge, numbers a right edge
+        assert!(list
+            .entries
+            .iter()
+            .all(|entry| entry.left == list.entries[0].left));
+        assert!(list.entries[0].number_right < list.entries[0].left);
+        assert!(boxes.iter().all(|b| b.left >= 0.0 && b.right <= 600.0));
+
+        let list =
+            ListLayout::new(&fonts, &items, ListMode::Replace, area, 48.0, &options).unwrap();
+        let boxes = list.text_boxes();
+        let middle = |b: &TextBox| (b.top + b.bottom) / 2.0;
+        assert!(boxes.iter().all(|b| (middle(b) - 500.0).abs() < 1.0));
+
+        let crowded = vec![ListItem::from("Too many words to fit ".repeat(20).as_str()); 10];
+        assert!(
+            ListLayout::new(&fonts, &crowded, ListMode::Accumulate, area, 48.0, &options).is_err()
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// EBU R128 normalization target for the audio track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessTarget {
+    pub integrated_lufs: f32, // e.g. -14 LUFS for most social platforms
+    pub true_peak_dbtp: f32,  // ceiling the gain may never push the peaks above
+}
+
+impl Default for LoudnessTarget {
+    fn default() -> Self {
+        Self {
+            integrated_lufs: -14.0,
+            true_peak_dbtp: -1.0,
+        }
+    }
+}
+
+/// Loudness of a piece of audio; silent audio measures negative infinity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    pub integrated_lufs: f64,
+    pub true_peak_dbtp: f64,
+}
+
+/// What normalization measured and changed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessReport {
+    pub input: LoudnessMeasurement,
+    pub output: LoudnessMeasurement,
+    pub applied_gain_db: f64,
+}
+
+const BLOCK_SECONDS: f64 = 0.4; // gating block length
+const STEP_SECONDS: f64 = 0.1; // 75% overlap between blocks
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+const OVERSAMPLING: usize = 4; // true-peak interpolation factor
+const INTERPOLATION_TAPS: i64 = 6; // samples on each side of an interpolated point
+
+/// Second order IIR section in direct form I
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2], // a1, a2 with a0 normalized to 1
+}
+
+impl Biquad {
+    fn process(&self, input: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        input
+            .iter()
+            .map(|&x| {
+                let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2
+                    - self.a[0] * y1
+                    - self.a[1] * y2;
+                x2 = x1;
+                x1 = x;
+                y2 = y1;
+                y1 = y;
+                y
+            })
+            .collect()
+    }
+}
+
+/// ITU-R BS.1770 K-weighting (high shelf followed by high pass) for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    // Stage 1: shelving filter modelling the acoustic effect of the head
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    // Stage 2: RLB high pass
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Integrated loudness with absolute and relative gating (EBU R128 / BS.1770-4)
+fn integrated_loudness(channels: &[Vec<f32>], sample_rate: u32) -> f64 {
+    let filters = k_weighting(sample_rate);
+    let weighted: Vec<Vec<f64>> = channels
+        .iter()
+        .map(|channel| {
+            let samples: Vec<f64> = channel.iter().map(|&s| s as f64).collect();
+            filters[1].process(&filters[0].process(&samples))
+        })
+        .collect();
+
+    let length = weighted.first().map_or(0, Vec::len);
+    let block = (BLOCK_SECONDS * sample_rate as f64) as usize;
+    let step = (STEP_SECONDS * sample_rate as f64) as usize;
+    if block == 0 || length < block {
+        return f64::NEG_INFINITY;
+    }
+
+    // Mean square per block, summed over channels (all weights are 1 for L/R)
+    let powers: Vec<f64> = (0..=(length - block) / step)
+        .map(|index| {
+            let start = index * step;
+            weighted
+                .iter()
+                .map(|channel| {
+                    channel[start..start + block]
+                        .iter()
+                        .map(|s| s * s)
+                        .sum::<f64>()
+                        / block as f64
+                })
+                .sum()
+        })
+        .collect();
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&power| loudness(power) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+
+    let Some(absolute) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+        return f64::NEG_INFINITY;
+    };
+    let relative_gate = loudness(absolute) + RELATIVE_GATE_LU;
+    gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS)).map_or(f64::NEG_INFINITY, loudness)
+}
+
+/// Peak of the signal reconstructed at 4x the sample rate, in dBTP
+fn true_peak(channels: &[Vec<f32>]) -> f64 {
+    let mut peak = 0.0f64;
+
+    for channel in channels {
+        let len = channel.len() as i64;
+        for n in 0..len {
+            peak = peak.max((channel[n as usize] as f64).abs());
+
+            // Hann-windowed sinc interpolation between sample n and n + 1
+            for phase in 1..OVERSAMPLING {
+                let fraction = phase as f64 / OVERSAMPLING as f64;
+                let mut value = 0.0;
+                for k in (1 - INTERPOLATION_TAPS)..=INTERPOLATION_TAPS {
+                    let index = n + k;
+                    if index < 0 || index >= len {
+                        continue;
+                    }
+                    let x = fraction - k as f64;
+                    let window = 0.5 * (1.0 + (PI * x / INTERPOLATION_TAPS as f64).cos());
+                    value += channel[index as usize] as f64 * sinc(x) * window;
+                }
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+
+    20.0 * peak.log10()
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Measure integrated loudness and true peak of planar audio
+pub(crate) fn measure(channels: &[Vec<f32>], sample_rate: u32) -> LoudnessMeasurement {
+    LoudnessMeasurement {
+        integrated_lufs: integrated_loudness(channels, sample_rate),
+        true_peak_dbtp: true_peak(channels),
+    }
+}
+
+/// Apply the gain that reaches the target loudness without exceeding the true-peak ceiling
+pub(crate) fn normalize(
+    channels: &mut [Vec<f32>],
+    sample_rate: u32,
+    target: &LoudnessTarget,
+) -> LoudnessReport {
+    let input = measure(channels, sample_rate);
+
+    // Silence has no loudness to normalize
+    if !input.integrated_lufs.is_finite() {
+        return LoudnessReport {
+            input,
+            output: input,
+            applied_gain_db: 0.0,
+        };
+    }
+
+    let gain_db = (target.integrated_lufs as f64 - input.integrated_lufs)
+        .min(target.true_peak_dbtp as f64 - input.true_peak_dbtp);
+    let gain = 10f64.powf(gain_db / 20.0) as f32;
+    for channel in channels.iter_mut() {
+        for sample in channel.iter_mut() {
+            *sample = (*sample * gain).clamp(-1.0, 1.0);
+        }
+    }
+
+    LoudnessReport {
+        input,
+        output: measure(channels, sample_rate),
+        applied_gain_db: gain_db,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo_sine(amplitude: f32, seconds: f32) -> Vec<Vec<f32>> {
+        let sample_rate = 48_000.0;
+        let channel: Vec<f32> = (0..(seconds * sample_rate) as usize)
+            .map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 997.0 * i as f32 / sample_rate).sin()
+            })
+            .collect();
+        vec![channel.clone(), channel]
+    }
+
+    #[test]
+    fn test_sine_loudness_matches_reference() {
+        // A 997 Hz stereo sine at amplitude 0.1 (-20 dBFS) measures -20 LUFS
+        let measurement = measure(&stereo_sine(0.1, 3.0), 48_000);
+        assert!((measurement.integrated_lufs + 20.0).abs() < 0.1);
+        assert!((measurement.true_peak_dbtp + 20.0).abs() < 0.1);
+
+        let silence = measure(&[vec![0.0; 48_000]], 48_000);
+        assert_eq!(silence.integrated_lufs, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_normalize_respects_true_peak_ceiling() {
+        let mut channels = stereo_sine(0.1, 3.0);
+        let report = normalize(&mut channels, 48_000, &LoudnessTarget::default());
+        assert!((report.output.integrated_lufs + 14.0).abs() < 0.1);
+        assert!((report.applied_gain_db - 6.0).abs() < 0.1);
+
+        // A low ceiling limits the gain before the loudness target is reached
+        let mut channels = stereo_sine(0.1, 3.0);
+        let target = LoudnessTarget {
+            integrated_lufs: -14.0,
+            true_peak_dbtp: -18.0,
+        };
+        let report = normalize(&mut channels, 48_000, &target);
+        assert!(report.output.true_peak_dbtp <= -18.0 + 0.01);
+        assert!(report.output.integrated_lufs < -17.0);
+    }
+}
@@ -1,28 +1,159 @@
+mod animation;
+mod audio;
+mod background;
+mod captions;
+mod chart;
+mod code;
+mod color;
+mod encoder;
+mod fonts;
+mod footage;
 pub mod generator;
+mod karaoke;
+mod layout;
+mod list;
+mod loudness;
+mod overlays;
+mod profile;
+mod progress;
+mod slideshow;
+pub mod text;
 pub mod validation;
+mod visualizer;
+mod watermark;
 
 use serde::{Deserialize, Serialize};
 
 // Re-export commonly used types
-pub use generator::VideoGenerator;
-pub use validation::VideoValidator;
+pub use animation::{FrameRenderer, TitleAnimation};
+pub use audio::AudioOptions;
+pub use background::{Background, GradientStop, ImageFit};
+pub use captions::{parse_srt, parse_webvtt, CaptionPosition, CaptionStyle, Captions, Cue};
+pub use chart::{ChartData, ChartKind, ChartSeries};
+pub use color::Color;
+pub use fonts::{FontChain, FontSource};
+pub use footage::BackgroundVideo;
+pub use generator::{GenerationReport, VideoGenerator};
+pub use karaoke::{KaraokeStyle, Transcript, TranscriptWord};
+pub use layout::{LayoutReport, TextBox};
+pub use list::{ListItem, ListMode};
+pub use loudness::{LoudnessMeasurement, LoudnessReport, LoudnessTarget};
+pub use overlays::OverlayAnchor;
+pub use profile::{OutputProfile, ProfilePreset, RateControl, SafeArea, SafeAreaPreset};
+pub use progress::{BarPosition, Countdown, ProgressBar};
+pub use text::{CaptionBox, Highlight, TextLayoutOptions, TextOutline, TextShadow, TextStyle};
+pub use validation::{SafeAreaViolation, VideoValidator};
+pub use visualizer::VisualizerStyle;
+pub use watermark::Watermark;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoSpec {
     pub title: String,
     pub template: VideoTemplate,
     pub duration_seconds: u32,
-    pub background_color: String, // hex color
-    pub text_color: String,       // hex color
+    pub background_color: Color, // "#1a1a1a", "white", "rgb(...)", "hsl(...)"; alpha ignored
+    #[serde(default)]
+    pub background: Option<Background>, // gradient or image drawn over background_color
+    pub text_color: Color,       // alpha blends the text over the background
     pub font_size: u32,
     pub audio_track: Option<String>, // path to audio file
+    pub subtitle: Option<String>,    // secondary line for TitleCard
+    pub accent_color: Option<Color>, // defaults to text_color
+    #[serde(default)]
+    pub text_layout: TextLayoutOptions, // wrapping margin and shrink-to-fit
+    #[serde(default)]
+    pub text_style: TextStyle, // outline, drop shadow and caption box
+    #[serde(default)]
+    pub audio_options: AudioOptions, // loop, offset, fades and gain for audio_track
+    #[serde(default)]
+    pub output_profile: OutputProfile, // resolution, frame rate and rate control
+    #[serde(default)]
+    pub animation: Option<TitleAnimation>, // SimpleText / TitleCard / Quote only, None = still
+    #[serde(default)]
+    pub fonts: Vec<FontSource>, // fallback chain, first match wins; DejaVu Sans is always last
+    #[serde(default)]
+    pub captions: Option<Captions>, // SRT/WebVTT or karaoke transcript, burned in over every template
+    #[serde(default)]
+    pub watermark: Option<Watermark>, // logo over every frame; None uses the generator's brand default
+    #[serde(default)]
+    pub progress_bar: Option<ProgressBar>, // fills over duration_seconds
+    #[serde(default)]
+    pub countdown: Option<Countdown>, // seconds left, in a corner of the safe area
+}
+
+impl Default for VideoSpec {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            template: VideoTemplate::SimpleText,
+            duration_seconds: 15,
+            background_color: Color::BLACK,
+            background: None,
+            text_color: Color::WHITE,
+            font_size: 48,
+            audio_track: None,
+            subtitle: None,
+            accent_color: None,
+            text_layout: TextLayoutOptions::default(),
+            text_style: TextStyle::default(),
+            audio_options: AudioOptions::default(),
+            output_profile: OutputProfile::default(),
+            animation: None,
+            fonts: Vec::new(),
+            captions: None,
+            watermark: None,
+            progress_bar: None,
+            countdown: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VideoTemplate {
     SimpleText,
     TitleCard,
-    Slideshow { slides: Vec<String> },
+    Slideshow {
+        slides: Vec<String>, // slide text, or path to a png/jpg image
+        #[serde(default)]
+        durations: Vec<f32>, // per-slide seconds adding up to duration_seconds, empty splits evenly
+        #[serde(default)]
+        transition: SlideTransition,
+    },
+    CodeSnippet {
+        language: String, // "rust", "python", "js", ...; others get strings, numbers and comments
+        code: String,
+        #[serde(default)]
+        reveal_lines: bool, // one line at a time over duration_seconds
+    },
+    Quote {
+        text: String, // without quotation marks, they are drawn around it
+        #[serde(default)]
+        author: Option<String>,
+    },
+    List {
+        heading: String,      // pinned at the top
+        items: Vec<ListItem>, // "text" or { "text", "seconds" }, numbered in order
+        #[serde(default)]
+        mode: ListMode, // Accumulate keeps earlier items, Replace shows one at a time
+    },
+    Chart {
+        #[serde(default)]
+        kind: ChartKind, // Bar, BarRace or Line, growing over duration_seconds
+        data: ChartData, // CSV file or inline series
+    },
+    Visualizer {
+        #[serde(default)]
+        style: VisualizerStyle, // spectrum bars or waveform of audio_track, under the title
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SlideTransition {
+    #[default]
+    Cut,
+    Crossfade {
+        seconds: f32,
+    },
 }
 
 #[derive(Debug)]
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use super::encoder::{FrameSource, SourceFrame};
+
+/// Something drawn on top of every frame of a template, e.g. captions or a logo
+pub(crate) trait FrameOverlay {
+    /// What the overlay shows at `time`; a still frame is only redrawn when this changes
+    fn state(&self, time: f64) -> u64;
+
+    fn draw(&self, image: &mut RgbImage, time: f64);
+}
+
+/// Corner (or center) of the safe area an overlay is pinned to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayAnchor {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl OverlayAnchor {
+    /// Top-left corner of a `size` box pinned `margin` px inside `bounds`
+    /// (left, top, right, bottom)
+    pub(crate) fn place(
+        self,
+        (left, top, right, bottom): (f32, f32, f32, f32),
+        margin: f32,
+        (width, height): (f32, f32),
+    ) -> (f32, f32) {
+        match self {
+            Self::TopLeft => (left + margin, top + margin),
+            Self::TopRight => (right - margin - width, top + margin),
+            Self::BottomLeft => (left + margin, bottom - margin - height),
+            Self::BottomRight => (right - margin - width, bottom - margin - height),
+            Self::Center => ((left + right - width) / 2.0, (top + bottom - height) / 2.0),
+        }
+    }
+}
+
+/// Draws overlays, in order, over the frames of another source
+pub(crate) struct OverlaidSource<'a> {
+    source: &'a mut dyn FrameSource,
+    overlays: Vec<Box<dyn FrameOverlay + 'a>>,
+    frame: Option<RgbImage>, // latest frame of `source`, without overlays
+    shown: Vec<u64>,         // overlay states the last frame was drawn with
+}
+
+impl<'a> OverlaidSource<'a> {
+    pub(crate) fn new(
+        source: &'a mut dyn FrameSource,
+        overlays: Vec<Box<dyn FrameOverlay + 'a>>,
+    ) -> Self {
+        Self {
+            source,
+            overlays,
+            frame: None,
+            shown: Vec::new(),
+        }
+    }
+}
+
+impl FrameSource for OverlaidSource<'_> {
+    fn frame(&mut self, index: u32, time: f64) -> Result<SourceFrame> {
+        let changed = match self.source.frame(index, time)? {
+            SourceFrame::Rgb(image) => {
+                self.frame = Some(image);
+                true
+            }
+            SourceFrame::Yuv(_) => {
+                return Err(anyhow::anyhow!("Overlays can only be drawn on RGB frames"));
+            }
+            SourceFrame::Repeat => false,
+        };
+
+        let states = self
+            .overlays
+            .iter()
+            .map(|overlay| overlay.state(time))
+            .collect::<Vec<_>>();
+        if !changed && states == self.shown {
+            return Ok(SourceFrame::Repeat);
+        }
+        self.shown = states;
+
+        let mut image = self
+            .frame
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Frame source repeated before the first frame"))?;
+        for overlay in &self.overlays {
+            overlay.draw(&mut image, time);
+        }
+        Ok(SourceFrame::Rgb(image))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// Lights pixel (x, 0) from second x on
+    struct Counter;
+
+    impl FrameOverlay for Counter {
+        fn state(&self, time: f64) -> u64 {
+            time as u64
+        }
+
+        fn draw(&self, image: &mut RgbImage, time: f64) {
+            image.put_pixel(time as u32, 0, Rgb([255, 255, 255]));
+        }
+    }
+
+    #[test]
+    fn test_still_frames_redraw_only_when_overlays_change() {
+        let mut still = |index: u32, _time: f64| -> Result<SourceFrame> {
+            Ok(match index {
+                0 => SourceFrame::Rgb(RgbImage::new(4, 1)),
+                _ => SourceFrame::Repeat,
+            })
+        };
+        let mut source = OverlaidSource::new(&mut still, vec![Box::new(Counter)]);
+
+        let SourceFrame::Rgb(first) = source.frame(0, 0.0).unwrap() else {
+            panic!("first frame must be drawn");
+        };
+        assert_eq!(first.get_pixel(0, 0)[0], 255);
+        assert!(matches!(source.frame(1, 0.5).unwrap(), SourceFrame::Repeat));
+
+        // Drawn on a fresh copy of the source frame, not on top of the previous one
+        let SourceFrame::Rgb(second) = source.frame(2, 1.0).unwrap() else {
+            panic!("overlay changed, frame must be redrawn");
+        };
+        assert_eq!(second.get_pixel(0, 0)[0], 0);
+        assert_eq!(second.get_pixel(1, 0)[0], 255);
+    }
+}
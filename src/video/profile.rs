@@ -0,0 +1,246 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// How the H.264 encoder spends bits
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RateControl {
+    /// Constant quality, 0 = lossless, 23 = x264 default, 51 = worst
+    Crf(u8),
+    /// Average bitrate with a ceiling, both in bits per second
+    Bitrate {
+        bit_rate: usize,
+        max_bit_rate: usize,
+    },
+}
+
+/// Named output formats for the common feed placements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfilePreset {
+    Vertical,  // 9:16, Shorts / Reels / TikTok
+    Square,    // 1:1
+    Landscape, // 16:9
+    Portrait,  // 4:5 feed posts
+}
+
+/// Share of the frame hidden behind platform UI on each edge, 0.0 - 1.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafeArea {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+/// Players whose overlays cover part of a vertical video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SafeAreaPreset {
+    YouTubeShorts,
+    TikTok,
+    InstagramReels,
+}
+
+impl SafeArea {
+    /// Title bar on top; channel name, caption and subscribe row at the bottom;
+    /// like, comment and share buttons down the right
+    pub fn youtube_shorts() -> Self {
+        Self {
+            top: 0.08,
+            bottom: 0.2,
+            left: 0.05,
+            right: 0.12,
+        }
+    }
+
+    /// Following / For You tabs on top; username, caption and sound at the
+    /// bottom; profile, like, comment and share buttons down the right
+    pub fn tiktok() -> Self {
+        Self {
+            top: 0.07,
+            bottom: 0.22,
+            left: 0.06,
+            right: 0.14,
+        }
+    }
+
+    /// Reels header on top; account, caption and audio at the bottom;
+    /// like, comment and share buttons down the right
+    pub fn instagram_reels() -> Self {
+        Self {
+            top: 0.07,
+            bottom: 0.2,
+            left: 0.05,
+            right: 0.12,
+        }
+    }
+
+    pub fn preset(preset: SafeAreaPreset) -> Self {
+        match preset {
+            SafeAreaPreset::YouTubeShorts => Self::youtube_shorts(),
+            SafeAreaPreset::TikTok => Self::tiktok(),
+            SafeAreaPreset::InstagramReels => Self::instagram_reels(),
+        }
+    }
+
+    /// Clear rectangle of a `width` x `height` frame as (left, top, right, bottom) in px
+    pub fn bounds(&self, width: u32, height: u32) -> (f32, f32, f32, f32) {
+        let (w, h) = (width as f32, height as f32);
+        (
+            w * self.left.clamp(0.0, 1.0),
+            h * self.top.clamp(0.0, 1.0),
+            w * (1.0 - self.right.clamp(0.0, 1.0)),
+            h * (1.0 - self.bottom.clamp(0.0, 1.0)),
+        )
+    }
+}
+
+/// Resolution, frame rate and encoder settings of a generated video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputProfile {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: u32,
+    pub rate_control: RateControl,
+    pub gop: u32, // keyframe interval in frames
+    pub qmin: i32,
+    pub qmax: i32,
+    #[serde(default)]
+    pub safe_area: SafeArea, // where text may go without being covered by player UI
+}
+
+impl OutputProfile {
+    /// 1080x1920 at 30fps and 2 Mbps, the original output format.
+    /// Text keeps clear of the YouTube Shorts overlays.
+    pub fn vertical() -> Self {
+        Self::with_size(1080, 1920).with_safe_area(SafeArea::youtube_shorts())
+    }
+
+    /// 1080x1080 at 30fps
+    pub fn square() -> Self {
+        Self::with_size(1080, 1080)
+    }
+
+    /// 1920x1080 at 30fps
+    pub fn landscape() -> Self {
+        Self::with_size(1920, 1080)
+    }
+
+    /// 1080x1350 at 30fps
+    pub fn portrait() -> Self {
+        Self::with_size(1080, 1350)
+    }
+
+    pub fn preset(preset: ProfilePreset) -> Self {
+        match preset {
+            ProfilePreset::Vertical => Self::vertical(),
+            ProfilePreset::Square => Self::square(),
+            ProfilePreset::Landscape => Self::landscape(),
+            ProfilePreset::Portrait => Self::portrait(),
+        }
+    }
+
+    /// Keep text inside `safe_area`, e.g. `SafeAreaPreset::TikTok.into()`
+    pub fn with_safe_area(mut self, safe_area: SafeArea) -> Self {
+        self.safe_area = safe_area;
+        self
+    }
+
+    fn with_size(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate: 30,
+            rate_control: RateControl::Bitrate {
+                bit_rate: 2_000_000,     // 2 Mbps
+                max_bit_rate: 2_500_000, // 2.5 Mbps max
+            },
+            gop: 30,
+            qmin: 10,
+            qmax: 51,
+            safe_area: SafeArea::default(),
+        }
+    }
+
+    /// Number of frames in a video of `duration_seconds`
+    pub fn total_frames(&self, duration_seconds: u32) -> u32 {
+        duration_seconds * self.frame_rate
+    }
+
+    /// Presentation time of frame `index` in seconds
+    pub fn frame_time(&self, index: u32) -> f64 {
+        index as f64 / self.frame_rate as f64
+    }
+
+    /// Reject settings the YUV420P H.264 encoder cannot handle
+    pub fn validate(&self) -> Result<()> {
+        if [self.width, self.height]
+            .iter()
+            .any(|side| *side == 0 || side % 2 == 1)
+        {
+            return Err(anyhow::anyhow!(
+                "Output size must be non-zero and even, got {}x{}",
+                self.width,
+                self.height
+            ));
+        }
+        if self.frame_rate == 0 {
+            return Err(anyhow::anyhow!("Output frame rate must be positive"));
+        }
+        if let RateControl::Crf(crf) = self.rate_control {
+            if crf > 51 {
+                return Err(anyhow::anyhow!("CRF must be between 0 and 51, got {}", crf));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for OutputProfile {
+    fn default() -> Self {
+        Self::vertical()
+    }
+}
+
+impl From<SafeAreaPreset> for SafeArea {
+    fn from(preset: SafeAreaPreset) -> Self {
+        Self::preset(preset)
+    }
+}
+
+impl From<ProfilePreset> for OutputProfile {
+    fn from(preset: ProfilePreset) -> Self {
+        Self::preset(preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_cover_feed_formats() {
+        let aspect = |profile: OutputProfile| profile.width as f32 / profile.height as f32;
+
+        assert_eq!(aspect(ProfilePreset::Vertical.into()), 9.0 / 16.0);
+        assert_eq!(aspect(ProfilePreset::Square.into()), 1.0);
+        assert_eq!(aspect(ProfilePreset::Landscape.into()), 16.0 / 9.0);
+        assert_eq!(aspect(ProfilePreset::Portrait.into()), 4.0 / 5.0);
+        assert_eq!(OutputProfile::default().total_frames(10), 300);
+    }
+
+    #[test]
+    fn test_validate_rejects_odd_sizes_and_bad_crf() {
+        let odd = OutputProfile {
+            width: 1079,
+            ..OutputProfile::vertical()
+        };
+        assert!(odd.validate().is_err());
+
+        let crf = OutputProfile {
+            rate_control: RateControl::Crf(60),
+            ..OutputProfile::square()
+        };
+        assert!(crf.validate().is_err());
+        assert!(OutputProfile::landscape().validate().is_ok());
+    }
+}
@@ -0,0 +1,273 @@
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use super::color::Color;
+use super::fonts::FontChain;
+use super::layout::TextBox;
+use super::overlays::{FrameOverlay, OverlayAnchor};
+use super::profile::OutputProfile;
+use super::text::{self, TextLayout, TextLayoutOptions, TextStyle};
+
+/// Thin bar across the safe area that fills up over the length of the video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressBar {
+    pub position: BarPosition,
+    pub thickness: u32,       // px
+    pub color: Option<Color>, // None = text_color
+    pub track_opacity: f32,   // 0.0 - 1.0, the unfilled rest of the bar in the same color
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            position: BarPosition::Bottom,
+            thickness: 8,
+            color: None,
+            track_opacity: 0.25,
+        }
+    }
+}
+
+/// Safe-area edge the progress bar runs along
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// Whole seconds left in the video, counting down to 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Countdown {
+    pub font_size: u32,
+    pub color: Option<Color>, // None = text_color
+    pub anchor: OverlayAnchor,
+    pub margin: u32, // px from the anchored edges of the safe area
+    pub text_style: TextStyle,
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self {
+            font_size: 72,
+            color: None,
+            anchor: OverlayAnchor::TopLeft, // clear of the default watermark corner
+            margin: 32,
+            text_style: TextStyle::default(),
+        }
+    }
+}
+
+/// A progress bar sized for one output profile and video length
+pub(crate) struct ProgressBarLayer {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    color: Color,
+    track: Color,
+    duration: f64,
+}
+
+impl ProgressBarLayer {
+    pub(crate) fn new(
+        bar: &ProgressBar,
+        profile: &OutputProfile,
+        text_color: Color,
+        duration_seconds: u32,
+    ) -> Self {
+        let (left, top, right, bottom) = profile.safe_area.bounds(profile.width, profile.height);
+        let height = bar.thickness.clamp(1, profile.height);
+        let top = match bar.position {
+            BarPosition::Top => top,
+            BarPosition::Bottom => bottom - height as f32,
+        };
+
+        let color = bar.color.unwrap_or(text_color);
+        let mut track = color;
+        track.a = (track.a as f32 * bar.track_opacity.clamp(0.0, 1.0)).round() as u8;
+
+        Self {
+            left: left.round() as u32,
+            top: top.max(0.0).round() as u32,
+            width: (right - left).round() as u32,
+            height,
+            color,
+            track,
+            duration: duration_seconds.max(1) as f64,
+        }
+    }
+
+    /// Filled width in px at `time`
+    fn filled(&self, time: f64) -> u32 {
+        let progress = (time / self.duration).clamp(0.0, 1.0);
+        (progress * self.width as f64).round() as u32
+    }
+}
+
+impl FrameOverlay for ProgressBarLayer {
+    fn state(&self, time: f64) -> u64 {
+        self.filled(time) as u64
+    }
+
+    fn draw(&self, image: &mut RgbImage, time: f64) {
+        let filled = self.filled(time);
+        let right = (self.left + self.width).min(image.width());
+        let bottom = (self.top + self.height).min(image.height());
+        for y in self.top..bottom {
+            for x in self.left..right {
+                let color = if x < self.left + filled {
+                    self.color
+                } else {
+                    self.track
+                };
+                color.blend(image.get_pixel_mut(x, y), 1.0);
+            }
+        }
+    }
+}
+
+/// A countdown placed for one output profile and video length
+pub(crate) struct CountdownLayer {
+    fonts: FontChain,
+    countdown: Countdown,
+    color: Color,
+    bounds: (f32, f32, f32, f32), // safe area
+    duration: f64,
+}
+
+impl CountdownLayer {
+    pub(crate) fn new(
+        countdown: &Countdown,
+        fonts: FontChain,
+        profile: &OutputProfile,
+        text_color: Color,
+        duration_seconds: u32,
+    ) -> Self {
+        Self {
+            fonts,
+            countdown: countdown.clone(),
+            color: countdown.color.unwrap_or(text_color),
+            bounds: profile.safe_area.bounds(profile.width, profile.height),
+            duration: duration_seconds as f64,
+        }
+    }
+
+    /// Seconds left at `time`, rounded up so the last second shows 1
+    fn remaining(&self, time: f64) -> u32 {
+        (self.duration - time).max(0.0).ceil() as u32
+    }
+
+    /// The number laid out on one line, with the top-left corner it is drawn at
+    fn place(&self, seconds: u32) -> (TextLayout, f32, f32) {
+        let options = TextLayoutOptions {
+            margin: 0,
+            ..Default::default()
+        };
+        let layout = text::layout_text(
+            &self.fonts,
+            &seconds.to_string(),
+            self.countdown.font_size as f32,
+            f32::MAX,
+            f32::MAX,
+            &options,
+        );
+        let (x, y) = self.countdown.anchor.place(
+            self.bounds,
+            self.countdown.margin as f32,
+            (layout.width(), layout.height()),
+        );
+        (layout, x, y)
+    }
+
+    /// Where the widest number, the one at the start, is drawn
+    pub(crate) fn text_box(&self) -> TextBox {
+        let (layout, x, y) = self.place(self.remaining(0.0));
+        TextBox::around("countdown", &layout, x + layout.width() / 2.0, y)
+    }
+}
+
+impl FrameOverlay for CountdownLayer {
+    fn state(&self, time: f64) -> u64 {
+        self.remaining(time) as u64
+    }
+
+    fn draw(&self, image: &mut RgbImage, time: f64) {
+        let seconds = self.remaining(time);
+        if seconds == 0 {
+            return;
+        }
+        let (layout, x, y) = self.place(seconds);
+        text::draw_styled_layout_prefix(
+            image,
+            &self.fonts,
+            &layout,
+            self.color,
+            &self.countdown.text_style,
+            x + layout.width() / 2.0,
+            y,
+            usize::MAX,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_progress_bar_fills_over_duration() {
+        let profile = OutputProfile {
+            width: 100,
+            height: 50,
+            ..OutputProfile::square()
+        };
+        let bar = ProgressBar {
+            position: BarPosition::Top,
+            thickness: 4,
+            color: Some(Color::rgb(255, 0, 0)),
+            track_opacity: 0.0,
+        };
+        let layer = ProgressBarLayer::new(&bar, &profile, Color::WHITE, 10);
+        assert_eq!(layer.state(0.0), 0);
+        assert_eq!(layer.state(2.5), 25);
+        assert_eq!(layer.state(12.0), 100);
+
+        let mut image = RgbImage::new(100, 50);
+        layer.draw(&mut image, 5.0);
+        assert_eq!(*image.get_pixel(49, 3), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(50, 3), Rgb([0, 0, 0])); // invisible track
+        assert_eq!(*image.get_pixel(10, 4), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_countdown_changes_once_per_second() {
+        let profile = OutputProfile {
+            width: 400,
+            height: 300,
+            ..OutputProfile::square()
+        };
+        let countdown = Countdown {
+            anchor: OverlayAnchor::TopRight,
+            ..Default::default()
+        };
+        let layer =
+            CountdownLayer::new(&countdown, FontChain::default(), &profile, Color::WHITE, 15);
+        assert_eq!(layer.state(0.0), 15);
+        assert_eq!(layer.state(0.5), 15);
+        assert_eq!(layer.state(14.2), 1);
+        assert_eq!(layer.state(15.0), 0);
+
+        // Right-aligned to the margin, so a shorter number doesn't drift off the edge
+        let text_box = layer.text_box();
+        assert!((text_box.right - 368.0).abs() < 1.0);
+        assert!((text_box.top - 32.0).abs() < 1.0);
+
+        let mut image = RgbImage::new(400, 300);
+        layer.draw(&mut image, 14.5);
+        assert!(image.pixels().any(|pixel| pixel[0] > 0));
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+
+use super::SlideTransition;
+
+/// What is visible at a given moment of a slideshow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SlideFrame {
+    Single(usize),
+    Crossfade {
+        from: usize,
+        to: usize,
+        progress: f32,
+    },
+}
+
+/// Maps timestamps to slides, honoring per-slide durations and transitions
+pub(crate) struct SlideTimeline {
+    ends: Vec<f32>, // cumulative end time of each slide in seconds
+    transition: SlideTransition,
+}
+
+impl SlideTimeline {
+    /// Timeline of `slide_count` slides over `total_seconds`. Explicit durations
+    /// must add up to the total, give or take one frame of `frame_seconds`.
+    pub(crate) fn new(
+        slide_count: usize,
+        durations: &[f32],
+        total_seconds: f32,
+        frame_seconds: f32,
+        transition: SlideTransition,
+    ) -> Result<Self> {
+        if slide_count == 0 {
+            return Err(anyhow::anyhow!(
+                "Slideshow template needs at least one slide"
+            ));
+        }
+
+        // Without explicit durations every slide gets an equal share
+        let durations = if durations.is_empty() {
+            vec![total_seconds / slide_count as f32; slide_count]
+        } else if durations.len() != slide_count {
+            return Err(anyhow::anyhow!(
+                "Slideshow has {} slides but {} durations",
+                slide_count,
+                durations.len()
+            ));
+        } else {
+            durations.to_vec()
+        };
+
+        if durations.iter().any(|d| d.is_nan() || *d <= 0.0) {
+            return Err(anyhow::anyhow!("Slide durations must be positive"));
+        }
+
+        // Otherwise the last slides are never shown or the last one is stretched
+        let sum: f32 = durations.iter().sum();
+        if (sum - total_seconds).abs() > frame_seconds {
+            return Err(anyhow::anyhow!(
+                "Slide durations add up to {}s but the video lasts {}s",
+                sum,
+                total_seconds
+            ));
+        }
+
+        if let SlideTransition::Crossfade { seconds } = transition {
+            if seconds.is_nan() || seconds < 0.0 {
+                return Err(anyhow::anyhow!("Crossfade length must not be negative"));
+            }
+        }
+
+        let mut elapsed = 0.0;
+        let ends = durations
+            .iter()
+            .map(|duration| {
+                elapsed += duration;
+                elapsed
+            })
+            .collect();
+
+        Ok(Self { ends, transition })
+    }
+
+    /// Slide(s) visible at `t` seconds; the last slide holds until the video ends
+    pub(crate) fn frame_at(&self, t: f32) -> SlideFrame {
+        let index = self
+            .ends
+            .iter()
+            .position(|end| t < *end)
+            .unwrap_or(self.ends.len() - 1);
+
+        if let SlideTransition::Crossfade { seconds } = self.transition {
+            let next = index + 1;
+            if seconds > 0.0 && next < self.ends.len() {
+                // The crossfade occupies the tail of the outgoing slide
+                let slide_start = if index == 0 {
+                    0.0
+                } else {
+                    self.ends[index - 1]
+                };
+                let fade_start = (self.ends[index] - seconds).max(slide_start);
+
+                if t >= fade_start {
+                    let progress =
+                        ((t - fade_start) / (self.ends[index] - fade_start)).clamp(0.0, 1.0);
+                    return SlideFrame::Crossfade {
+                        from: index,
+                        to: next,
+                        progress,
+                    };
+                }
+            }
+        }
+
+        SlideFrame::Single(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 30.0;
+
+    #[test]
+    fn test_even_split_with_cuts() {
+        let timeline = SlideTimeline::new(3, &[], 9.0, FRAME, SlideTransition::Cut).unwrap();
+
+        assert_eq!(timeline.frame_at(0.0), SlideFrame::Single(0));
+        assert_eq!(timeline.frame_at(2.9), SlideFrame::Single(0));
+        assert_eq!(timeline.frame_at(3.0), SlideFrame::Single(1));
+        assert_eq!(timeline.frame_at(8.9), SlideFrame::Single(2));
+        assert_eq!(timeline.frame_at(20.0), SlideFrame::Single(2));
+    }
+
+    #[test]
+    fn test_per_slide_durations_with_crossfade() {
+        let timeline = SlideTimeline::new(
+            2,
+            &[4.0, 6.0],
+            10.0,
+            FRAME,
+            SlideTransition::Crossfade { seconds: 1.0 },
+        )
+        .unwrap();
+
+        assert_eq!(timeline.frame_at(2.0), SlideFrame::Single(0));
+        assert_eq!(
+            timeline.frame_at(3.5),
+            SlideFrame::Crossfade {
+                from: 0,
+                to: 1,
+                progress: 0.5
+            }
+        );
+        assert_eq!(timeline.frame_at(4.0), SlideFrame::Single(1));
+
+        assert!(SlideTimeline::new(2, &[4.0], 10.0, FRAME, SlideTransition::Cut).is_err());
+        assert!(SlideTimeline::new(0, &[], 10.0, FRAME, SlideTransition::Cut).is_err());
+    }
+
+    #[test]
+    fn test_durations_must_add_up_to_the_video() {
+        let timeline = |durations: &[f32]| {
+            SlideTimeline::new(
+                durations.len(),
+                durations,
+                10.0,
+                FRAME,
+                SlideTransition::Cut,
+            )
+        };
+
+        // The third slide would start after the video ends
+        let Err(error) = timeline(&[6.0, 4.0, 3.0]) else {
+            panic!("durations longer than the video");
+        };
+        assert!(error.to_string().contains("13s"), "{}", error);
+        // The last slide would be stretched to fill the rest
+        assert!(timeline(&[2.0, 3.0]).is_err());
+        // Rounding within a frame is fine
+        assert!(timeline(&[3.33, 3.33, 3.34]).is_ok());
+        assert!(timeline(&[3.33, 3.33, 3.33]).is_ok());
+    }
+}
@@ -0,0 +1,1004 @@
+use ab_glyph::{point, Font, Glyph, PxScale, ScaleFont};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use super::color::Color;
+use super::fonts::FontChain;
+
+/// Controls how text is wrapped and sized inside the frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextLayoutOptions {
+    pub margin: u32,              // horizontal padding on each side of the frame in px
+    pub max_lines: Option<usize>, // lines shrink_to_fit aims for, never truncates; None = unlimited
+    pub shrink_to_fit: bool,      // reduce the font size until the text fits
+    pub min_font_size: u32,       // smallest size shrink_to_fit may use
+    pub line_spacing: f32,        // multiple of the font size between baselines
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        Self {
+            margin: 60,
+            max_lines: None,
+            shrink_to_fit: false,
+            min_font_size: 24,
+            line_spacing: 1.2,
+        }
+    }
+}
+
+/// Effects that keep text readable over busy backgrounds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextStyle {
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+    pub caption_box: Option<CaptionBox>, // drawn behind each line
+}
+
+impl TextStyle {
+    fn is_plain(&self) -> bool {
+        self.outline.is_none() && self.shadow.is_none() && self.caption_box.is_none()
+    }
+}
+
+/// Stroke around every glyph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextOutline {
+    pub color: Color,
+    pub width: f32, // px
+}
+
+impl Default for TextOutline {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            width: 3.0,
+        }
+    }
+}
+
+/// Blurred copy of the text (and outline) behind it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextShadow {
+    pub color: Color,
+    pub offset_x: i32, // px, positive moves right
+    pub offset_y: i32, // px, positive moves down
+    pub blur: f32,     // blur radius in px, 0 = hard shadow
+    pub opacity: f32,  // 0.0 - 1.0, on top of the color's own alpha
+}
+
+impl Default for TextShadow {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            offset_x: 4,
+            offset_y: 4,
+            blur: 6.0,
+            opacity: 0.6,
+        }
+    }
+}
+
+/// Rounded, semi-transparent box behind each line of text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionBox {
+    pub color: Color,
+    pub opacity: f32,       // 0.0 - 1.0, on top of the color's own alpha
+    pub padding: u32,       // px between the text and the box edge
+    pub corner_radius: u32, // px
+}
+
+impl Default for CaptionBox {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            opacity: 0.55,
+            padding: 16,
+            corner_radius: 16,
+        }
+    }
+}
+
+/// A glyph and the index of the font in the chain that draws it
+#[derive(Debug, Clone)]
+struct PlacedGlyph {
+    font: usize,
+    glyph: Glyph,
+}
+
+/// A single wrapped line with glyphs positioned relative to its own origin
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    pub text: String,
+    glyphs: Vec<PlacedGlyph>, // baseline at y = 0, pen starts at x = 0
+    pub ink_min_x: f32,       // left edge of the rendered pixels
+    pub ink_max_x: f32,       // right edge of the rendered pixels
+}
+
+impl TextLine {
+    /// Width of the rendered pixels, which is what centering is based on
+    pub fn ink_width(&self) -> f32 {
+        (self.ink_max_x - self.ink_min_x).max(0.0)
+    }
+}
+
+/// Result of wrapping and sizing text against a width budget
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    pub lines: Vec<TextLine>,
+    pub font_size: f32,
+    pub line_height: f32, // distance between consecutive baselines
+    pub ascent: f32,
+    pub descent: f32, // negative, below the baseline
+}
+
+impl TextLayout {
+    /// Height of the whole block from the first ascender to the last descender
+    pub fn height(&self) -> f32 {
+        if self.lines.is_empty() {
+            return 0.0;
+        }
+        self.ascent - self.descent + (self.lines.len() - 1) as f32 * self.line_height
+    }
+
+    /// Number of characters across all lines, the unit of `draw_layout_prefix`
+    pub fn char_count(&self) -> usize {
+        self.lines.iter().map(|line| line.glyphs.len()).sum()
+    }
+
+    /// Width of the widest line
+    pub fn width(&self) -> f32 {
+        self.lines
+            .iter()
+            .map(TextLine::ink_width)
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Advance width of `text` including kerning between consecutive glyphs
+/// of the same font
+pub fn measure_text(fonts: &FontChain, font_size: f32, text: &str) -> f32 {
+    let scale = PxScale::from(font_size);
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let (index, id) = fonts.glyph(c);
+        let scaled = fonts.font(index).as_scaled(scale);
+        if let Some((previous_index, previous_id)) = previous {
+            if previous_index == index {
+                width += scaled.kern(previous_id, id);
+            }
+        }
+        width += scaled.h_advance(id);
+        previous = Some((index, id));
+    }
+
+    width
+}
+
+/// Ink bounds of `text` set on one line as (left, top, right, bottom),
+/// relative to the pen start on the baseline; `top` is negative above it
+pub(crate) fn ink_bounds(fonts: &FontChain, font_size: f32, text: &str) -> (f32, f32, f32, f32) {
+    let line = layout_line(fonts, font_size, text.to_string());
+    let (mut top, mut bottom) = (f32::MAX, f32::MIN);
+    for placed in line.glyphs {
+        if let Some(outlined) = fonts.font(placed.font).outline_glyph(placed.glyph) {
+            top = top.min(outlined.px_bounds().min.y);
+            bottom = bottom.max(outlined.px_bounds().max.y);
+        }
+    }
+
+    // Nothing visible, e.g. only spaces
+    if top > bottom {
+        (top, bottom) = (0.0, 0.0);
+    }
+    (line.ink_min_x, top, line.ink_max_x, bottom)
+}
+
+/// Greedy word wrap; returns the lines and whether any word had to be broken mid-word
+fn wrap_text(fonts: &FontChain, font_size: f32, text: &str, max_width: f32) -> (Vec<String>, bool) {
+    let mut lines = Vec::new();
+    let mut broke_word = false;
+
+    // Explicit line breaks always start a new line
+    for paragraph in text.lines() {
+        let mut current = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", current, word)
+            };
+
+            if measure_text(fonts, font_size, &candidate) <= max_width {
+                current = candidate;
+                continue;
+            }
+
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+
+            if measure_text(fonts, font_size, word) <= max_width {
+                current = word.to_string();
+                continue;
+            }
+
+            // The word alone is too wide, break it between characters
+            broke_word = true;
+            for c in word.chars() {
+                current.push(c);
+                if measure_text(fonts, font_size, &current) > max_width
+                    && current.chars().count() > 1
+                {
+                    current.pop();
+                    lines.push(std::mem::replace(&mut current, c.to_string()));
+                }
+            }
+        }
+
+        lines.push(current);
+    }
+
+    (lines, broke_word)
+}
+
+/// Position the glyphs of one line and compute its ink bounds
+fn layout_line(fonts: &FontChain, font_size: f32, text: String) -> TextLine {
+    let scale = PxScale::from(font_size);
+
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+    let mut ink_min_x = f32::MAX;
+    let mut ink_max_x = f32::MIN;
+
+    for c in text.chars() {
+        let (index, id) = fonts.glyph(c);
+        let font = fonts.font(index);
+        let scaled = font.as_scaled(scale);
+        if let Some((previous_index, previous_id)) = previous {
+            if previous_index == index {
+                caret += scaled.kern(previous_id, id);
+            }
+        }
+
+        let glyph = id.with_scale_and_position(scale, point(caret, 0.0));
+        if let Some(outlined) = font.outline_glyph(glyph.clone()) {
+            let bounds = outlined.px_bounds();
+            ink_min_x = ink_min_x.min(bounds.min.x);
+            ink_max_x = ink_max_x.max(bounds.max.x);
+        }
+
+        caret += scaled.h_advance(id);
+        previous = Some((index, id));
+        glyphs.push(PlacedGlyph { font: index, glyph });
+    }
+
+    // Blank lines have no ink, fall back to the advance box
+    if ink_min_x > ink_max_x {
+        ink_min_x = 0.0;
+        ink_max_x = caret;
+    }
+
+    TextLine {
+        text,
+        glyphs,
+        ink_min_x,
+        ink_max_x,
+    }
+}
+
+/// Wrap `text` to `max_width`, shrinking the font when the options ask for it
+/// and the result exceeds `max_height` or `max_lines`. Nothing is cut off: text
+/// that still has more than `max_lines` lines at the minimum size keeps them all.
+pub fn layout_text(
+    fonts: &FontChain,
+    text: &str,
+    font_size: f32,
+    max_width: f32,
+    max_height: f32,
+    options: &TextLayoutOptions,
+) -> TextLayout {
+    // At least 1px, or a text that never fits would shrink until the size underflows
+    let min_font_size = (options.min_font_size as f32).max(1.0).min(font_size);
+    let mut size = font_size;
+
+    loop {
+        let (lines, broke_word) = wrap_text(fonts, size, text, max_width);
+        let layout = build_layout(fonts, size, lines, options);
+
+        let too_many_lines = options
+            .max_lines
+            .is_some_and(|max_lines| layout.lines.len() > max_lines);
+        let fits = !broke_word && !too_many_lines && layout.height() <= max_height;
+
+        if fits || !options.shrink_to_fit || size <= min_font_size {
+            return layout;
+        }
+
+        size = (size * 0.9).max(min_font_size);
+    }
+}
+
+/// Vertical metrics come from the primary font of the chain
+fn build_layout(
+    fonts: &FontChain,
+    font_size: f32,
+    lines: Vec<String>,
+    options: &TextLayoutOptions,
+) -> TextLayout {
+    let scaled = fonts.primary().as_scaled(PxScale::from(font_size));
+
+    TextLayout {
+        lines: lines
+            .into_iter()
+            .map(|line| layout_line(fonts, font_size, line))
+            .collect(),
+        font_size,
+        line_height: font_size * options.line_spacing,
+        ascent: scaled.ascent(),
+        descent: scaled.descent(),
+    }
+}
+
+/// Draw every line centered on `center_x`, with the top of the block at `top_y`
+pub fn draw_layout(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    layout: &TextLayout,
+    color: Color,
+    center_x: f32,
+    top_y: f32,
+) {
+    draw_layout_prefix(image, fonts, layout, color, center_x, top_y, usize::MAX);
+}
+
+/// Like `draw_layout`, but only the first `visible_chars` glyphs are drawn.
+/// Lines stay where the full text puts them, so a reveal does not shift the text around.
+pub fn draw_layout_prefix(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    layout: &TextLayout,
+    color: Color,
+    center_x: f32,
+    top_y: f32,
+    visible_chars: usize,
+) {
+    for (font, glyph) in positioned_glyphs(layout, center_x, top_y, visible_chars) {
+        draw_glyph(image, fonts.font(font), glyph, color);
+    }
+}
+
+/// `draw_layout_prefix` with the caption box, shadow and outline of `style`
+/// drawn underneath the text, in that order.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_styled_layout_prefix(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    layout: &TextLayout,
+    color: Color,
+    style: &TextStyle,
+    center_x: f32,
+    top_y: f32,
+    visible_chars: usize,
+) {
+    if style.is_plain() {
+        draw_layout_prefix(image, fonts, layout, color, center_x, top_y, visible_chars);
+        return;
+    }
+
+    // Everything is drawn from coverage masks around the block
+    let mut mask = Mask::around(layout, center_x, top_y, effect_margin(style));
+    draw_caption_boxes(image, &mask, layout, style, center_x, top_y, visible_chars);
+
+    for (font, glyph) in positioned_glyphs(layout, center_x, top_y, visible_chars) {
+        mask.add_glyph(fonts.font(font), glyph);
+    }
+
+    draw_shadow_and_outline(image, style, &mask);
+    mask.blend_onto(image, color, 1.0, (0, 0));
+}
+
+/// Draw `text` on a single line starting at `x`, with its baseline at
+/// `baseline`; returns the pen position after the last character
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_text_run(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    text: &str,
+    font_size: f32,
+    x: f32,
+    baseline: f32,
+    color: Color,
+) -> f32 {
+    let line = layout_line(fonts, font_size, text.to_string());
+    for placed in line.glyphs {
+        let mut glyph = placed.glyph;
+        glyph.position = point(glyph.position.x + x, baseline);
+        draw_glyph(image, fonts.font(placed.font), glyph, color);
+    }
+    x + measure_text(fonts, font_size, text)
+}
+
+/// Fill a rectangle with anti-aliased corners of `radius`
+pub(crate) fn fill_rounded_rect(
+    image: &mut RgbImage,
+    (left, top): (f32, f32),
+    (right, bottom): (f32, f32),
+    radius: f32,
+    color: Color,
+) {
+    let x = left.floor() as i32;
+    let y = top.floor() as i32;
+    let width = (right.ceil() as i32 - x).max(0) as usize;
+    let height = (bottom.ceil() as i32 - y).max(0) as usize;
+    let mut mask = Mask {
+        x,
+        y,
+        width,
+        height,
+        data: vec![0.0; width * height],
+    };
+    mask.add_rounded_rect((left, top), (right, bottom), radius);
+    mask.blend_onto(image, color, 1.0, (0, 0));
+}
+
+/// Anti-aliased line from `a` to `b`, `thickness` px wide with round ends
+pub(crate) fn draw_line(
+    image: &mut RgbImage,
+    a: (f32, f32),
+    b: (f32, f32),
+    thickness: f32,
+    color: Color,
+) {
+    let half = thickness / 2.0;
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = (dx * dx + dy * dy).max(f32::EPSILON);
+
+    let x_range = (a.0.min(b.0) - half - 1.0).max(0.0) as u32
+        ..((a.0.max(b.0) + half + 1.0).max(0.0) as u32).min(image.width());
+    let y_range = (a.1.min(b.1) - half - 1.0).max(0.0) as u32
+        ..((a.1.max(b.1) + half + 1.0).max(0.0) as u32).min(image.height());
+    for y in y_range {
+        for x in x_range.clone() {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let t = (((px - a.0) * dx + (py - a.1) * dy) / length_squared).clamp(0.0, 1.0);
+            let distance = (px - a.0 - t * dx).hypot(py - a.1 - t * dy);
+            let coverage = (half + 0.5 - distance).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                color.blend(image.get_pixel_mut(x, y), coverage);
+            }
+        }
+    }
+}
+
+/// Characters of a layout drawn in their own color and size
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub range: Range<usize>, // characters as counted by `TextLayout::char_count`
+    pub color: Color,
+    pub scale: f32, // grows the characters around their center, 1.0 = unchanged
+}
+
+/// `draw_styled_layout_prefix` for the whole layout, with the characters of
+/// `highlight` drawn in its color and scale on top of the same box, shadow and outline
+#[allow(clippy::too_many_arguments)]
+pub fn draw_highlighted_layout(
+    image: &mut RgbImage,
+    fonts: &FontChain,
+    layout: &TextLayout,
+    color: Color,
+    style: &TextStyle,
+    center_x: f32,
+    top_y: f32,
+    highlight: &Highlight,
+) {
+    let mut glyphs = positioned_glyphs(layout, center_x, top_y, usize::MAX).collect::<Vec<_>>();
+    let range = highlight.range.start.min(glyphs.len())..highlight.range.end.min(glyphs.len());
+    scale_glyphs(fonts, layout, &mut glyphs, range.clone(), highlight.scale);
+
+    if style.is_plain() {
+        for (index, (font, glyph)) in glyphs.into_iter().enumerate() {
+            let color = if range.contains(&index) {
+                highlight.color
+            } else {
+                color
+            };
+            draw_glyph(image, fonts.font(font), glyph, color);
+        }
+        return;
+    }
+
+    let growth = layout.font_size * (highlight.scale - 1.0).max(0.0);
+    let mut plain = Mask::around(layout, center_x, top_y, effect_margin(style) + growth);
+    draw_caption_boxes(image, &plain, layout, style, center_x, top_y, usize::MAX);
+
+    // Outline and shadow surround both parts, each is filled in its own color
+    let mut lit = plain.empty_like();
+    let mut all = plain.empty_like();
+    for (index, (font, glyph)) in glyphs.into_iter().enumerate() {
+        let font = fonts.font(font);
+        all.add_glyph(font, glyph.clone());
+        if range.contains(&index) {
+            lit.add_glyph(font, glyph);
+        } else {
+            plain.add_glyph(font, glyph);
+        }
+    }
+
+    draw_shadow_and_outline(image, style, &all);
+    plain.blend_onto(image, color, 1.0, (0, 0));
+    lit.blend_onto(image, highlight.color, 1.0, (0, 0));
+}
+
+/// Room the outline, shadow and caption box of `style` need around the glyphs
+fn effect_margin(style: &TextStyle) -> f32 {
+    let padding = style
+        .caption_box
+        .as_ref()
+        .map_or(0, |caption_box| caption_box.padding) as f32;
+    style.outline.as_ref().map_or(0.0, |outline| outline.width)
+        + style.shadow.as_ref().map_or(0.0, |shadow| {
+            shadow.blur + shadow.offset_x.abs().max(shadow.offset_y.abs()) as f32
+        })
+        + padding
+        + 2.0
+}
+
+/// Caption boxes behind every line that has a visible character
+fn draw_caption_boxes(
+    image: &mut RgbImage,
+    area: &Mask,
+    layout: &TextLayout,
+    style: &TextStyle,
+    center_x: f32,
+    top_y: f32,
+    visible_chars: usize,
+) {
+    let Some(caption_box) = &style.caption_box else {
+        return;
+    };
+    let padding = caption_box.padding as f32;
+    let mut remaining = visible_chars;
+
+    // One mask for all boxes, so boxes of neighbouring lines do not darken their overlap
+    let mut boxes = area.empty_like();
+    for (index, line) in layout.lines.iter().enumerate() {
+        // Boxes wrap the whole line as soon as any of it is visible
+        if remaining == 0 || line.glyphs.is_empty() {
+            break;
+        }
+        remaining = remaining.saturating_sub(line.glyphs.len());
+
+        let baseline = top_y + layout.ascent + index as f32 * layout.line_height;
+        let half_width = line.ink_width() / 2.0;
+        boxes.add_rounded_rect(
+            (
+                center_x - half_width - padding,
+                baseline - layout.ascent - padding,
+            ),
+            (
+                center_x + half_width + padding,
+                baseline - layout.descent + padding,
+            ),
+            caption_box.corner_radius as f32,
+        );
+    }
+    boxes.blend_onto(image, caption_box.color, caption_box.opacity, (0, 0));
+}
+
+/// Shadow and outline of the glyphs in `mask`, in that order
+fn draw_shadow_and_outline(image: &mut RgbImage, style: &TextStyle, mask: &Mask) {
+    let outlined = match &style.outline {
+        Some(outline) if outline.width > 0.0 => Some((mask.dilate(outline.width), outline)),
+        _ => None,
+    };
+
+    if let Some(shadow) = &style.shadow {
+        // The shadow is cast by everything that is drawn, outline included
+        let caster = outlined.as_ref().map_or(mask, |(dilated, _)| dilated);
+        caster.blur(shadow.blur).blend_onto(
+            image,
+            shadow.color,
+            shadow.opacity,
+            (shadow.offset_x, shadow.offset_y),
+        );
+    }
+    if let Some((dilated, outline)) = &outlined {
+        dilated.blend_onto(image, outline.color, 1.0, (0, 0));
+    }
+}
+
+/// Grow the glyphs in `range` by `scale` around the center of their run on
+/// each line, moving the rest of that line aside to make room
+fn scale_glyphs(
+    fonts: &FontChain,
+    layout: &TextLayout,
+    glyphs: &mut [(usize, Glyph)],
+    range: Range<usize>,
+    scale: f32,
+) {
+    if scale == 1.0 || range.is_empty() {
+        return;
+    }
+
+    let mut start = range.start;
+    while start < range.end {
+        let baseline = glyphs[start].1.position.y;
+        let end = glyphs[start..range.end]
+            .iter()
+            .position(|(_, glyph)| glyph.position.y != baseline)
+            .map_or(range.end, |offset| start + offset);
+
+        let (last_font, last) = &glyphs[end - 1];
+        let right = last.position.x
+            + fonts
+                .font(*last_font)
+                .as_scaled(last.scale)
+                .h_advance(last.id);
+        let left = glyphs[start].1.position.x;
+        let anchor_x = (left + right) / 2.0;
+        let anchor_y = baseline - layout.ascent / 2.0;
+        let shift = (right - left) * (scale - 1.0) / 2.0;
+
+        for (index, (_, glyph)) in glyphs.iter_mut().enumerate() {
+            if glyph.position.y != baseline {
+                continue;
+            }
+            if index < start {
+                glyph.position.x -= shift;
+            } else if index >= end {
+                glyph.position.x += shift;
+            } else {
+                glyph.scale = PxScale {
+                    x: glyph.scale.x * scale,
+                    y: glyph.scale.y * scale,
+                };
+                glyph.position = point(
+                    anchor_x + (glyph.position.x - anchor_x) * scale,
+                    anchor_y + (glyph.position.y - anchor_y) * scale,
+                );
+            }
+        }
+        start = end;
+    }
+}
+
+/// Glyphs of the first `visible_chars` characters at their final position,
+/// with the index of the font drawing each one
+fn positioned_glyphs(
+    layout: &TextLayout,
+    center_x: f32,
+    top_y: f32,
+    visible_chars: usize,
+) -> impl Iterator<Item = (usize, Glyph)> + '_ {
+    layout
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(move |(index, line)| {
+            let baseline = top_y + layout.ascent + index as f32 * layout.line_height;
+            let offset_x = center_x - (line.ink_min_x + line.ink_max_x) / 2.0;
+            line.glyphs.iter().map(move |placed| {
+                let mut glyph = placed.glyph.clone();
+                glyph.position = point(glyph.position.x + offset_x, baseline);
+                (placed.font, glyph)
+            })
+        })
+        .take(visible_chars)
+}
+
+/// Glyph coverage over a region of the image, 0.0 to 1.0 per pixel
+struct Mask {
+    x: i32, // image position of the top-left cell
+    y: i32,
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+impl Mask {
+    /// Empty mask covering the layout block plus `margin` on every side
+    fn around(layout: &TextLayout, center_x: f32, top_y: f32, margin: f32) -> Self {
+        let half_width = layout.width() / 2.0 + layout.font_size + margin;
+        let x = (center_x - half_width).floor() as i32;
+        let y = (top_y - layout.font_size - margin).floor() as i32;
+        let width = (half_width * 2.0).ceil() as usize + 1;
+        let height = (layout.height() + (layout.font_size + margin) * 2.0).ceil() as usize + 1;
+        Self {
+            x,
+            y,
+            width,
+            height,
+            data: vec![0.0; width * height],
+        }
+    }
+
+    fn empty_like(&self) -> Self {
+        Self {
+            data: vec![0.0; self.data.len()],
+            ..*self
+        }
+    }
+
+    fn get(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return 0.0;
+        }
+        self.data[y as usize * self.width + x as usize]
+    }
+
+    fn add_glyph<F: Font>(&mut self, font: &F, glyph: Glyph) {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            return;
+        };
+        let bounds = outlined.px_bounds();
+        let origin_x = bounds.min.x as i32 - self.x;
+        let origin_y = bounds.min.y as i32 - self.y;
+
+        outlined.draw(|x, y, coverage| {
+            let mx = origin_x + x as i32;
+            let my = origin_y + y as i32;
+            if mx < 0 || my < 0 || mx as usize >= self.width || my as usize >= self.height {
+                return;
+            }
+            let cell = &mut self.data[my as usize * self.width + mx as usize];
+            *cell = (*cell + coverage).min(1.0);
+        });
+    }
+
+    /// Cover a rectangle in image coordinates with rounded, anti-aliased corners
+    fn add_rounded_rect(
+        &mut self,
+        (left, top): (f32, f32),
+        (right, bottom): (f32, f32),
+        radius: f32,
+    ) {
+        let radius = radius
+            .min((right - left) / 2.0)
+            .min((bottom - top) / 2.0)
+            .max(0.0);
+
+        for my in 0..self.height {
+            for mx in 0..self.width {
+                // Distance outside the rectangle shrunk by the radius, measured from pixel centers
+                let px = (self.x + mx as i32) as f32 + 0.5;
+                let py = (self.y + my as i32) as f32 + 0.5;
+                let dx = (left + radius - px).max(px - (right - radius)).max(0.0);
+                let dy = (top + radius - py).max(py - (bottom - radius)).max(0.0);
+                let corner = if dx > 0.0 || dy > 0.0 {
+                    radius - (dx * dx + dy * dy).sqrt() + 0.5
+                } else {
+                    1.0 // inside, even when the radius is too small to cover a whole pixel
+                };
+
+                // Straight edges are faded by how much of the pixel is covered
+                let coverage = corner
+                    .min(px + 0.5 - left)
+                    .min(right - (px - 0.5))
+                    .min(py + 0.5 - top)
+                    .min(bottom - (py - 0.5))
+                    .clamp(0.0, 1.0);
+
+                let cell = &mut self.data[my * self.width + mx];
+                *cell = cell.max(coverage);
+            }
+        }
+    }
+
+    /// Grow the covered area by `radius` pixels in every direction
+    fn dilate(&self, radius: f32) -> Self {
+        let reach = radius.ceil() as i32 + 1;
+        let offsets: Vec<(i32, i32, f32)> = (-reach..=reach)
+            .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+            .filter_map(|(dx, dy)| {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                let weight = (radius + 0.5 - distance).clamp(0.0, 1.0);
+                (weight > 0.0).then_some((dx, dy, weight))
+            })
+            .collect();
+
+        let mut dilated = self.empty_like();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                dilated.data[y as usize * self.width + x as usize] = offsets
+                    .iter()
+                    .map(|&(dx, dy, weight)| self.get(x + dx, y + dy) * weight)
+                    .fold(0.0, f32::max);
+            }
+        }
+        dilated
+    }
+
+    /// Soften the edges over roughly `radius` pixels with three box blur passes,
+    /// a cheap approximation of a gaussian blur
+    fn blur(&self, radius: f32) -> Self {
+        let box_radius = (radius / 3.0).round() as i32;
+        let mut blurred = Self {
+            data: self.data.clone(),
+            ..*self
+        };
+        if box_radius < 1 {
+            return blurred;
+        }
+
+        for _ in 0..3 {
+            blurred = blurred
+                .box_blur(box_radius, true)
+                .box_blur(box_radius, false);
+        }
+        blurred
+    }
+
+    fn box_blur(&self, radius: i32, horizontal: bool) -> Self {
+        let mut blurred = self.empty_like();
+        let window = (radius * 2 + 1) as f32;
+
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let sum: f32 = (-radius..=radius)
+                    .map(|d| {
+                        if horizontal {
+                            self.get(x + d, y)
+                        } else {
+                            self.get(x, y + d)
+                        }
+                    })
+                    .sum();
+                blurred.data[y as usize * self.width + x as usize] = sum / window;
+            }
+        }
+        blurred
+    }
+
+    /// Blend `color` over the image wherever the mask covers it, shifted by `offset`
+    fn blend_onto(&self, image: &mut RgbImage, color: Color, opacity: f32, offset: (i32, i32)) {
+        let (image_width, image_height) = (image.width() as i32, image.height() as i32);
+
+        for my in 0..self.height {
+            for mx in 0..self.width {
+                let coverage = self.data[my * self.width + mx];
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let px = self.x + mx as i32 + offset.0;
+                let py = self.y + my as i32 + offset.1;
+                if px < 0 || py < 0 || px >= image_width || py >= image_height {
+                    continue;
+                }
+                color.blend(
+                    image.get_pixel_mut(px as u32, py as u32),
+                    coverage * opacity,
+                );
+            }
+        }
+    }
+}
+
+/// Rasterize one glyph, blending its coverage over the existing pixels
+fn draw_glyph<F: Font>(image: &mut RgbImage, font: &F, glyph: Glyph, color: Color) {
+    let Some(outlined) = font.outline_glyph(glyph) else {
+        return;
+    };
+    let bounds = outlined.px_bounds();
+    let (width, height) = (image.width() as i32, image.height() as i32);
+
+    outlined.draw(|x, y, coverage| {
+        let px = bounds.min.x as i32 + x as i32;
+        let py = bounds.min.y as i32 + y as i32;
+        if px < 0 || py < 0 || px >= width || py >= height {
+            return;
+        }
+
+        color.blend(image.get_pixel_mut(px as u32, py as u32), coverage);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn font() -> FontChain {
+        FontChain::default()
+    }
+
+    #[test]
+    fn test_long_text_wraps_within_width() {
+        let font = font();
+        let options = TextLayoutOptions::default();
+        let layout = layout_text(
+            &font,
+            "Very Long Title That Tests Text Wrapping Across Several Lines",
+            64.0,
+            960.0,
+            1800.0,
+            &options,
+        );
+
+        assert!(layout.lines.len() > 1);
+        for line in &layout.lines {
+            assert!(measure_text(&font, 64.0, &line.text) <= 960.0);
+        }
+    }
+
+    #[test]
+    fn test_shrink_to_fit_reduces_font_size() {
+        let font = font();
+        let options = TextLayoutOptions {
+            max_lines: Some(1),
+            shrink_to_fit: true,
+            ..Default::default()
+        };
+        let layout = layout_text(
+            &font,
+            "Fits On One Line Eventually",
+            96.0,
+            960.0,
+            1800.0,
+            &options,
+        );
+
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.font_size < 96.0);
+        assert!(layout.width() <= 960.0);
+
+        // A minimum of 0 still stops at 1px when the text can never fit
+        let unbounded = TextLayoutOptions {
+            min_font_size: 0,
+            ..options
+        };
+        let layout = layout_text(&font, "Too tall", 96.0, 960.0, 0.0, &unbounded);
+        assert_eq!(layout.font_size, 1.0);
+    }
+
+    #[test]
+    fn test_styled_text_draws_box_and_outline() {
+        let fonts = font();
+        let layout = layout_text(&fonts, "Hi", 64.0, 400.0, 400.0, &Default::default());
+        let blue = Rgb([0, 0, 255]);
+        let white = Color::WHITE;
+        let top = 60.0;
+
+        let style = TextStyle {
+            outline: Some(TextOutline {
+                color: Color::rgb(255, 0, 0),
+                width: 4.0,
+            }),
+            shadow: Some(TextShadow::default()),
+            caption_box: Some(CaptionBox::default()),
+        };
+        let mut image = RgbImage::from_pixel(400, 240, blue);
+        draw_styled_layout_prefix(&mut image, &fonts, &layout, white, &style, 200.0, top, 2);
+
+        // Half transparent black box above the glyphs, rounded off in its corner
+        assert_eq!(*image.get_pixel(200, top as u32 - 8), Rgb([0, 0, 115]));
+        let left = (200.0 - layout.width() / 2.0 - 16.0) as u32;
+        assert_eq!(*image.get_pixel(left, top as u32 - 16), blue);
+
+        // The outline adds red that plain drawing never produces
+        let red = |image: &RgbImage| image.pixels().filter(|p| p[0] > 200 && p[1] < 50).count();
+        let mut plain = RgbImage::from_pixel(400, 240, blue);
+        draw_layout(&mut plain, &fonts, &layout, white, 200.0, top);
+        assert!(red(&image) > 100);
+        assert_eq!(red(&plain), 0);
+    }
+
+    #[test]
+    fn test_non_ascii_measured_by_glyph() {
+        let font = font();
+        // Byte length differs from character count, width must follow the glyphs
+        let accented = measure_text(&font, 48.0, "ééé");
+        let plain = measure_text(&font, 48.0, "eee");
+        assert!((accented - plain).abs() < 1.0);
+    }
+}
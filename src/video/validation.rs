@@ -2,12 +2,93 @@ use anyhow::Result;
 use ffmpeg_next as ffmpeg;
 use std::path::Path;
 
-use super::VideoValidation;
+use super::audio;
+use super::layout::{LayoutReport, TextBox};
+use super::loudness::{self, LoudnessMeasurement};
+use super::{OutputProfile, SafeArea, VideoValidation};
 
 pub struct VideoValidator;
 
+/// Text reaching into a part of the frame covered by platform UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafeAreaViolation {
+    pub text: TextBox,
+    pub overflow: (f32, f32, f32, f32), // px past the left, top, right and bottom edge
+}
+
 impl VideoValidator {
+    /// Duration of the video stream in seconds, falling back to the container duration
+    pub fn probe_duration(video_path: &Path) -> Result<f64> {
+        let context = ffmpeg::format::input(&video_path)?;
+
+        let video_stream = context
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or_else(|| anyhow::anyhow!("No video stream found"))?;
+
+        // Convert from stream time base to seconds
+        let duration = video_stream.duration();
+        if duration > 0 {
+            return Ok(duration as f64 * f64::from(video_stream.time_base()));
+        }
+
+        // If video duration is not reliable, check container duration
+        let container_duration = context.duration();
+        if container_duration > 0 {
+            return Ok(container_duration as f64 / ffmpeg::ffi::AV_TIME_BASE as f64);
+        }
+
+        Ok(0.0)
+    }
+
+    /// Integrated loudness and true peak of the audio track, `None` when there is no audio
+    pub fn measure_loudness(video_path: &Path) -> Result<Option<LoudnessMeasurement>> {
+        let context = ffmpeg::format::input(&video_path)?;
+        if context.streams().best(ffmpeg::media::Type::Audio).is_none() {
+            return Ok(None);
+        }
+
+        let buffer = audio::decode_audio(video_path, 0.0, None)?;
+        Ok(Some(loudness::measure(
+            &buffer.channels,
+            audio::SAMPLE_RATE,
+        )))
+    }
+
+    /// Text boxes of `report` that leave `safe_area`, with how far they go past each edge
+    pub fn check_safe_area(report: &LayoutReport, safe_area: &SafeArea) -> Vec<SafeAreaViolation> {
+        let (left, top, right, bottom) = safe_area.bounds(report.width, report.height);
+
+        report
+            .boxes
+            .iter()
+            .filter_map(|text| {
+                let overflow = (
+                    (left - text.left).max(0.0),
+                    (top - text.top).max(0.0),
+                    (text.right - right).max(0.0),
+                    (text.bottom - bottom).max(0.0),
+                );
+                // Sub-pixel overhang from rounding is not worth reporting
+                let (l, t, r, b) = overflow;
+                (l.max(t).max(r).max(b) >= 1.0).then(|| SafeAreaViolation {
+                    text: text.clone(),
+                    overflow,
+                })
+            })
+            .collect()
+    }
+
+    /// Validate against the default vertical 1080x1920 profile
     pub fn validate_video(video_path: &Path) -> Result<VideoValidation> {
+        Self::validate_video_with_profile(video_path, &OutputProfile::vertical())
+    }
+
+    /// Validate against the profile the video was generated with
+    pub fn validate_video_with_profile(
+        video_path: &Path,
+        profile: &OutputProfile,
+    ) -> Result<VideoValidation> {
         let context = ffmpeg::format::input(&video_path)?;
 
         // Find video stream
@@ -20,28 +101,15 @@ impl VideoValidator {
             ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
         let video_decoder = video_context.decoder().video()?;
 
-        // Check dimensions (should be 1080x1920)
-        let correct_dimensions = video_decoder.width() == 1080 && video_decoder.height() == 1920;
-
-        // Check duration (convert from stream time base to seconds)
-        let duration_seconds = {
-            let duration = video_stream.duration();
-            let time_base = video_stream.time_base();
-            if duration > 0 {
-                (duration as f64 * time_base.numerator() as f64 / time_base.denominator() as f64) as u32
-            } else {
-                // If video duration is not reliable, check container duration
-                let container_duration = context.duration();
-                if container_duration > 0 {
-                    (container_duration as f64 / ffmpeg::ffi::AV_TIME_BASE as f64) as u32
-                } else {
-                    0
-                }
-            }
-        };
+        // Check dimensions against the profile
+        let correct_dimensions =
+            video_decoder.width() == profile.width && video_decoder.height() == profile.height;
+
+        // Check duration, rounded to whole seconds
+        let duration_seconds = Self::probe_duration(video_path)?.round() as u32;
 
         // Allow test videos to be shorter (5+ seconds) but still enforce production range (10-60s)
-        let duration_in_range = duration_seconds >= 5 && duration_seconds <= 60;
+        let duration_in_range = (5..=60).contains(&duration_seconds);
 
         // Check file size (<50MB)
         let file_size = std::fs::metadata(video_path)?.len();
@@ -67,7 +135,7 @@ impl VideoValidator {
 mod tests {
     use super::*;
     use crate::video::generator::VideoGenerator;
-    use crate::video::{VideoSpec, VideoTemplate};
+    use crate::video::{VideoSpec, VideoTemplate, VisualizerStyle};
     use tempfile::TempDir;
 
     #[test]
@@ -82,10 +150,11 @@ mod tests {
             title: "Validation Test".to_string(),
             template: VideoTemplate::SimpleText,
             duration_seconds: 15,
-            background_color: "#000000".to_string(),
-            text_color: "#ffffff".to_string(),
+            background_color: "#000000".parse().unwrap(),
+            text_color: "#ffffff".parse().unwrap(),
             font_size: 32,
             audio_track: None,
+            ..Default::default()
         };
 
         let video_path = generator.generate_video(&spec).unwrap();
@@ -97,4 +166,159 @@ mod tests {
         assert!(validation.is_playable);
         // Note: has_audio will be false since we're not adding audio yet
     }
+
+    #[test]
+    fn test_duration_metadata_matches_spec() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let spec = VideoSpec {
+            title: "Duration Metadata Test".to_string(),
+            template: VideoTemplate::SimpleText,
+            duration_seconds: 6,
+            background_color: "#000000".parse().unwrap(),
+            text_color: "#ffffff".parse().unwrap(),
+            font_size: 32,
+            audio_track: None,
+            ..Default::default()
+        };
+
+        let video_path = generator.generate_video(&spec).unwrap();
+        let one_frame = 1.0 / 30.0;
+
+        // Stream metadata used to report ~0.004s for correctly encoded frames
+        let stream_duration = VideoValidator::probe_duration(&video_path).unwrap();
+        assert!(
+            (stream_duration - spec.duration_seconds as f64).abs() <= one_frame,
+            "stream duration {} differs from {}",
+            stream_duration,
+            spec.duration_seconds
+        );
+
+        let context = ffmpeg::format::input(&video_path).unwrap();
+        let container_duration = context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+        assert!(
+            (container_duration - spec.duration_seconds as f64).abs() <= one_frame,
+            "container duration {} differs from {}",
+            container_duration,
+            spec.duration_seconds
+        );
+    }
+
+    #[test]
+    fn test_normalized_audio_loudness() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let target = crate::video::LoudnessTarget {
+            integrated_lufs: -16.0,
+            true_peak_dbtp: -1.0,
+        };
+        let spec = VideoSpec {
+            title: "Loudness Test".to_string(),
+            template: VideoTemplate::SimpleText,
+            duration_seconds: 6,
+            audio_track: Some("test_tone_10s.wav".to_string()),
+            audio_options: crate::video::AudioOptions {
+                normalize: Some(target),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let report = generator.generate_video_with_report(&spec).unwrap();
+        let loudness = report.loudness.unwrap();
+        assert!(loudness.output.true_peak_dbtp <= -1.0 + 0.01);
+
+        // AAC encoding shifts the level slightly, the result stays close to the target
+        let measured = VideoValidator::measure_loudness(&report.output_path)
+            .unwrap()
+            .unwrap();
+        assert!((measured.integrated_lufs - loudness.output.integrated_lufs).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_square_profile_validation() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let temp_path = temp_dir.path().join("temp");
+
+        let generator = VideoGenerator::new(&output_dir, &temp_path).unwrap();
+
+        let profile = OutputProfile {
+            rate_control: crate::video::RateControl::Crf(23),
+            ..OutputProfile::square()
+        };
+        let spec = VideoSpec {
+            title: "Square Profile Test".to_string(),
+            template: VideoTemplate::SimpleText,
+            duration_seconds: 5,
+            output_profile: profile.clone(),
+            ..Default::default()
+        };
+
+        let video_path = generator.generate_video(&spec).unwrap();
+        let validation =
+            VideoValidator::validate_video_with_profile(&video_path, &profile).unwrap();
+        assert!(validation.correct_dimensions);
+        assert!(validation.duration_in_range);
+
+        // The same file does not pass as a vertical video
+        let vertical = VideoValidator::validate_video(&video_path).unwrap();
+        assert!(!vertical.correct_dimensions);
+    }
+
+    #[test]
+    fn test_safe_area_violations_from_layout_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator =
+            VideoGenerator::new(temp_dir.path().join("output"), temp_dir.path().join("temp"))
+                .unwrap();
+
+        let spec = VideoSpec {
+            title: "Centered inside the player overlays".to_string(),
+            template: VideoTemplate::TitleCard,
+            subtitle: Some("Subtitle".to_string()),
+            ..Default::default()
+        };
+        let mut report = generator.layout_report(&spec).unwrap();
+        assert_eq!(report.boxes.len(), 2);
+        let safe_area = spec.output_profile.safe_area;
+        assert!(VideoValidator::check_safe_area(&report, &safe_area).is_empty());
+
+        // A lower third under the like button: past the bottom and right edges
+        report.boxes.push(TextBox {
+            label: "lower third".to_string(),
+            left: 600.0,
+            top: 1600.0,
+            right: 1000.0,
+            bottom: 1700.0,
+        });
+        let violations = VideoValidator::check_safe_area(&report, &safe_area);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].text.label, "lower third");
+        let (left, top, right, bottom) = violations[0].overflow;
+        assert_eq!((left, top), (0.0, 0.0));
+        assert!((right - 49.6).abs() < 0.01); // 1080 * (1 - 0.12) = 950.4
+        assert!((bottom - 164.0).abs() < 0.01); // 1920 * (1 - 0.2) = 1536
+
+        // The spectrum is checked along with the title above it
+        let visualizer = VideoSpec {
+            title: "Episode 12".to_string(),
+            template: VideoTemplate::Visualizer {
+                style: VisualizerStyle::default(),
+            },
+            ..Default::default()
+        };
+        let report = generator.layout_report(&visualizer).unwrap();
+        let labels: Vec<_> = report.boxes.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, ["title", "visualizer"]);
+        assert!(VideoValidator::check_safe_area(&report, &safe_area).is_empty());
+    }
 }
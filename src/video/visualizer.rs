@@ -0,0 +1,284 @@
+use anyhow::Result;
+use image::RgbImage;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::animation::FrameRenderer;
+use super::audio::{AudioBuffer, SAMPLE_RATE};
+use super::color::Color;
+use super::text;
+
+/// Samples analysed per frame, about 46ms at 44.1kHz
+const WINDOW: usize = 2048;
+
+/// Frequencies the spectrum spans, spaced logarithmically like pitch
+const MIN_FREQUENCY: f32 = 40.0;
+const MAX_FREQUENCY: f32 = 16_000.0;
+
+/// Quietest level a spectrum bar shows, in dB below full scale
+const FLOOR_DB: f32 = -60.0;
+
+/// How fast spectrum bars fall back after a peak, in bar heights per second
+const FALL_PER_SECOND: f32 = 2.5;
+
+/// What the Visualizer template draws from the audio track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VisualizerStyle {
+    /// Bars for frequency bands from 40Hz to 16kHz, rising with their level
+    Spectrum {
+        #[serde(default = "default_bars")]
+        bars: u32,
+    },
+    /// The wave itself around the current moment, as on an oscilloscope
+    Waveform,
+}
+
+fn default_bars() -> u32 {
+    48
+}
+
+impl Default for VisualizerStyle {
+    fn default() -> Self {
+        VisualizerStyle::Spectrum {
+            bars: default_bars(),
+        }
+    }
+}
+
+/// Audio drawn frame by frame into an area of the frame, over a still background
+pub(crate) struct VisualizerRenderer {
+    background: RgbImage,
+    style: VisualizerStyle,
+    samples: Vec<f32>, // channels mixed to mono, scaled so the loudest sample is 1.0
+    area: (f32, f32, f32, f32),
+    color: Color,
+    fft: Arc<dyn Fft<f32>>,
+    bands: Vec<Range<usize>>, // FFT bins per spectrum bar
+    levels: Vec<f32>,         // bar heights of the last frame, 0.0 - 1.0
+    previous: Option<f64>,    // time of the last frame
+}
+
+impl VisualizerRenderer {
+    /// Draw `audio` into `area` (left, top, right, bottom) in `color`. The
+    /// audio must be the prepared track that is encoded, so the picture is in sync.
+    pub(crate) fn new(
+        background: RgbImage,
+        style: VisualizerStyle,
+        audio: &AudioBuffer,
+        area: (f32, f32, f32, f32),
+        color: Color,
+    ) -> Result<Self> {
+        let bars = match style {
+            VisualizerStyle::Spectrum { bars: 0 } => {
+                return Err(anyhow::anyhow!(
+                    "Visualizer spectrum needs at least one bar"
+                ));
+            }
+            VisualizerStyle::Spectrum { bars } => bars as usize,
+            VisualizerStyle::Waveform => 0,
+        };
+
+        let mut samples: Vec<f32> = (0..audio.len())
+            .map(|index| {
+                audio
+                    .channels
+                    .iter()
+                    .map(|channel| channel[index])
+                    .sum::<f32>()
+                    / audio.channels.len() as f32
+            })
+            .collect();
+        let peak = samples
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        if peak > 0.0 {
+            samples.iter_mut().for_each(|sample| *sample /= peak);
+        }
+
+        Ok(Self {
+            background,
+            style,
+            samples,
+            area,
+            color,
+            fft: FftPlanner::new().plan_fft_forward(WINDOW),
+            bands: band_bins(bars),
+            levels: vec![0.0; bars],
+            previous: None,
+        })
+    }
+
+    /// Sample at `index`, silence before the start and after the end
+    fn sample(&self, index: i64) -> f32 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.samples.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Level of every band in the window centered on `time`, 0.0 - 1.0
+    fn spectrum(&self, time: f64) -> Vec<f32> {
+        let start = (time * SAMPLE_RATE as f64).round() as i64 - WINDOW as i64 / 2;
+        let mut buffer: Vec<Complex<f32>> = (0..WINDOW)
+            .map(|index| {
+                let hann =
+                    0.5 - 0.5 * (2.0 * std::f32::consts::PI * index as f32 / WINDOW as f32).cos();
+                Complex::new(self.sample(start + index as i64) * hann, 0.0)
+            })
+            .collect();
+        self.fft.process(&mut buffer);
+
+        // A full-scale sine peaks at WINDOW / 4 through the Hann window
+        self.bands
+            .iter()
+            .map(|bins| {
+                let magnitude = buffer[bins.clone()]
+                    .iter()
+                    .map(|bin| bin.norm())
+                    .fold(0.0, f32::max);
+                let db = 20.0 * (magnitude / (WINDOW as f32 / 4.0)).max(1e-9).log10();
+                ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    fn draw_spectrum(&self, image: &mut RgbImage) {
+        let (left, top, right, bottom) = self.area;
+        let slot = (right - left) / self.levels.len() as f32;
+        let width = slot * 0.7;
+        for (index, level) in self.levels.iter().enumerate() {
+            let x = left + slot * index as f32 + (slot - width) / 2.0;
+            // Silent bars stay visible as rounded dots
+            let height = (level * (bottom - top)).max(width);
+            text::fill_rounded_rect(
+                image,
+                (x, bottom - height),
+                (x + width, bottom),
+                width / 2.0,
+                self.color,
+            );
+        }
+    }
+
+    fn draw_waveform(&self, image: &mut RgbImage, time: f64) {
+        let (left, top, right, bottom) = self.area;
+        let middle = (top + bottom) / 2.0;
+        let thickness = ((bottom - top) / 100.0).max(3.0);
+        let segments = 256;
+        let start = (time * SAMPLE_RATE as f64).round() as i64 - WINDOW as i64 / 2;
+
+        let point = |segment: usize| {
+            let index = start + (segment * WINDOW / segments) as i64;
+            (
+                left + (right - left) * segment as f32 / segments as f32,
+                middle - self.sample(index) * (bottom - top) / 2.0,
+            )
+        };
+        for segment in 0..segments {
+            text::draw_line(
+                image,
+                point(segment),
+                point(segment + 1),
+                thickness,
+                self.color,
+            );
+        }
+    }
+}
+
+/// FFT bins of `bars` bands spaced logarithmically from MIN_FREQUENCY to
+/// MAX_FREQUENCY, each at least one bin wide
+fn band_bins(bars: usize) -> Vec<Range<usize>> {
+    let bin = |frequency: f32| (frequency * WINDOW as f32 / SAMPLE_RATE as f32).round() as usize;
+    let edge = |band: usize| {
+        MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(band as f32 / bars as f32)
+    };
+    (0..bars)
+        .map(|band| {
+            let start = bin(edge(band));
+            start..bin(edge(band + 1)).max(start + 1)
+        })
+        .collect()
+}
+
+impl FrameRenderer for VisualizerRenderer {
+    fn render(&mut self, time: f64) -> Result<Option<RgbImage>> {
+        let mut frame = self.background.clone();
+        match self.style {
+            VisualizerStyle::Spectrum { .. } => {
+                // Bars jump up to a peak and fall back gradually
+                let elapsed = self
+                    .previous
+                    .map_or(f32::MAX, |previous| (time - previous) as f32);
+                let fall = FALL_PER_SECOND * elapsed.max(0.0);
+                let spectrum = self.spectrum(time);
+                for (level, current) in self.levels.iter_mut().zip(spectrum) {
+                    *level = current.max(*level - fall);
+                }
+                self.draw_spectrum(&mut frame);
+            }
+            VisualizerStyle::Waveform => self.draw_waveform(&mut frame, time),
+        }
+        self.previous = Some(time);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f32, seconds: f32) -> AudioBuffer {
+        let samples = (seconds * SAMPLE_RATE as f32) as usize;
+        let wave: Vec<f32> = (0..samples)
+            .map(|index| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * index as f32;
+                0.5 * (phase / SAMPLE_RATE as f32).sin()
+            })
+            .collect();
+        AudioBuffer {
+            channels: [wave.clone(), wave],
+        }
+    }
+
+    #[test]
+    fn test_spectrum_peaks_at_tone_frequency() {
+        let audio = tone(1000.0, 1.0);
+        let style = VisualizerStyle::Spectrum { bars: 24 };
+        let area = (0.0, 0.0, 240.0, 100.0);
+        let mut renderer =
+            VisualizerRenderer::new(RgbImage::new(240, 100), style, &audio, area, Color::WHITE)
+                .unwrap();
+
+        let levels = renderer.spectrum(0.5);
+        let loudest = (0..levels.len())
+            .max_by(|a, b| levels[*a].total_cmp(&levels[*b]))
+            .unwrap();
+        let bins = &renderer.bands[loudest];
+        let frequency = |bin: usize| bin as f32 * SAMPLE_RATE as f32 / WINDOW as f32;
+        assert!(frequency(bins.start) <= 1000.0 && 1000.0 < frequency(bins.end));
+        assert!(levels[loudest] > 0.9, "{}", levels[loudest]);
+        assert!(levels[0] < 0.2, "{}", levels[0]);
+
+        // After the tone ends, the bars fall back instead of vanishing
+        renderer.render(0.99).unwrap();
+        let before = renderer.levels[loudest];
+        renderer.render(1.1).unwrap();
+        let after = renderer.levels[loudest];
+        assert!(after < before && after > 0.5, "{} -> {}", before, after);
+    }
+
+    #[test]
+    fn test_bands_cover_the_range_in_order() {
+        let bands = band_bins(48);
+        assert_eq!(bands.len(), 48);
+        assert!(bands.iter().all(|bins| bins.end > bins.start));
+        assert!(bands.windows(2).all(|pair| pair[1].start >= pair[0].start));
+        assert_eq!(bands[0].start, 2); // 40Hz in bins of ~21.5Hz
+        assert_eq!(bands[47].end, 743); // 16kHz
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use image::{RgbImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::overlays::{FrameOverlay, OverlayAnchor};
+use super::profile::OutputProfile;
+
+/// Logo composited over every frame, keeping the transparency of the PNG
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub path: String,
+    #[serde(default)]
+    pub anchor: OverlayAnchor,
+    #[serde(default = "default_margin")]
+    pub margin: u32, // px from the anchored edges of the safe area
+    #[serde(default = "default_scale")]
+    pub scale: f32, // logo width as a fraction of the frame width
+    #[serde(default = "default_opacity")]
+    pub opacity: f32, // 0.0 - 1.0, on top of the PNG's own alpha
+}
+
+fn default_margin() -> u32 {
+    32
+}
+
+fn default_scale() -> f32 {
+    0.15
+}
+
+fn default_opacity() -> f32 {
+    0.9
+}
+
+impl Watermark {
+    /// `path` in the top right corner at the default size and opacity
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            anchor: OverlayAnchor::default(),
+            margin: default_margin(),
+            scale: default_scale(),
+            opacity: default_opacity(),
+        }
+    }
+}
+
+/// A watermark scaled and positioned for one output profile
+pub(crate) struct WatermarkLayer {
+    logo: RgbaImage, // premultiplied alpha
+    x: i64,
+    y: i64,
+    opacity: f32,
+}
+
+impl WatermarkLayer {
+    pub(crate) fn new(watermark: &Watermark, profile: &OutputProfile) -> Result<Self> {
+        let mut logo = image::open(&watermark.path)
+            .map_err(|e| anyhow::anyhow!("Failed to load watermark {}: {}", watermark.path, e))?
+            .to_rgba8();
+
+        // Resampling straight alpha bleeds the color of invisible pixels into the edges
+        premultiply(&mut logo);
+        let width = ((profile.width as f32 * watermark.scale).round() as u32).max(1);
+        let height =
+            ((logo.height() as f32 * width as f32 / logo.width() as f32).round() as u32).max(1);
+        let logo = imageops::resize(&logo, width, height, FilterType::CatmullRom);
+
+        let bounds = profile.safe_area.bounds(profile.width, profile.height);
+        let (x, y) = watermark.anchor.place(
+            bounds,
+            watermark.margin as f32,
+            (width as f32, height as f32),
+        );
+
+        Ok(Self {
+            logo,
+            x: x.round() as i64,
+            y: y.round() as i64,
+            opacity: watermark.opacity.clamp(0.0, 1.0),
+        })
+    }
+}
+
+impl FrameOverlay for WatermarkLayer {
+    fn state(&self, _time: f64) -> u64 {
+        0 // never changes
+    }
+
+    fn draw(&self, image: &mut RgbImage, _time: f64) {
+        composite_premultiplied(image, &self.logo, self.x, self.y, self.opacity);
+    }
+}
+
+/// Scale every color channel by its pixel's alpha
+fn premultiply(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for c in 0..3 {
+            pixel[c] = ((pixel[c] as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// Source-over composite of a premultiplied RGBA image with its top-left
+/// corner at (`x`, `y`); parts outside the frame are clipped
+fn composite_premultiplied(frame: &mut RgbImage, source: &RgbaImage, x: i64, y: i64, opacity: f32) {
+    for (sx, sy, pixel) in source.enumerate_pixels() {
+        let (fx, fy) = (x + sx as i64, y + sy as i64);
+        if fx < 0 || fy < 0 || fx >= frame.width() as i64 || fy >= frame.height() as i64 {
+            continue;
+        }
+
+        let alpha = pixel[3] as f32 / 255.0 * opacity;
+        if alpha <= 0.0 {
+            continue;
+        }
+        let target = frame.get_pixel_mut(fx as u32, fy as u32);
+        for c in 0..3 {
+            let value = target[c] as f32 * (1.0 - alpha) + pixel[c] as f32 * opacity;
+            target[c] = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, Rgba};
+
+    #[test]
+    fn test_alpha_composite_of_translucent_logo() {
+        let mut logo = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 128]));
+        logo.put_pixel(1, 0, Rgba([0, 255, 0, 0])); // invisible, its color must not show
+        premultiply(&mut logo);
+
+        let mut frame = RgbImage::from_pixel(3, 1, Rgb([0, 0, 200]));
+        composite_premultiplied(&mut frame, &logo, 1, 0, 1.0);
+        assert_eq!(*frame.get_pixel(0, 0), Rgb([0, 0, 200]));
+        assert_eq!(*frame.get_pixel(1, 0), Rgb([128, 0, 100]));
+        assert_eq!(*frame.get_pixel(2, 0), Rgb([0, 0, 200]));
+
+        // Opacity scales the logo's own alpha; pixels past the edge are clipped
+        let mut faded = RgbImage::from_pixel(1, 1, Rgb([0, 0, 200]));
+        composite_premultiplied(&mut faded, &logo, 0, 0, 0.5);
+        assert_eq!(*faded.get_pixel(0, 0), Rgb([64, 0, 150]));
+    }
+
+    #[test]
+    fn test_anchor_places_logo_inside_safe_area() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("logo.png");
+        RgbaImage::from_pixel(40, 20, Rgba([255, 255, 255, 255]))
+            .save(&path)
+            .unwrap();
+
+        let profile = OutputProfile::vertical(); // 1080x1920, Shorts safe area
+        let watermark = Watermark {
+            anchor: OverlayAnchor::BottomRight,
+            margin: 10,
+            scale: 0.1,
+            ..Watermark::new(path.to_string_lossy())
+        };
+        let layer = WatermarkLayer::new(&watermark, &profile).unwrap();
+        assert_eq!(layer.logo.dimensions(), (108, 54));
+        // Right edge of the safe area at 950.4, bottom at 1536
+        assert_eq!((layer.x, layer.y), (832, 1472));
+    }
+}